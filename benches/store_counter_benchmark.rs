@@ -0,0 +1,99 @@
+#[macro_use]
+extern crate criterion;
+
+use criterion::Criterion;
+use rand::{thread_rng, Rng};
+use restricted_sparse_merkle_tree::{
+    blake2b::Blake2bHasher,
+    compressed_store::CompressedSparseMerkleTree,
+    default_store::DefaultStore,
+    error::Result,
+    traits::Store,
+    tree::{BranchKey, BranchNode, LeafNode, SparseMerkleTree},
+    H256,
+};
+
+const LEAVES_COUNT: usize = 1_000;
+
+type SMT = SparseMerkleTree<Blake2bHasher, H256, CountingStore<H256>>;
+type CompressedSMT = CompressedSparseMerkleTree<Blake2bHasher, H256>;
+
+/// wraps `DefaultStore` and counts `insert_branch`/`remove_branch` calls, so the
+/// dense tree's node churn can be compared against `CompressedSparseMerkleTree`'s
+#[derive(Default)]
+struct CountingStore<V> {
+    inner: DefaultStore<V>,
+    insert_branch_count: usize,
+    remove_branch_count: usize,
+}
+
+impl<V: Clone> Store<V> for CountingStore<V> {
+    fn get_branch(&self, branch_key: &BranchKey) -> Result<Option<BranchNode>> {
+        self.inner.get_branch(branch_key)
+    }
+    fn get_leaf(&self, leaf_key: &H256) -> Result<Option<LeafNode<V>>> {
+        self.inner.get_leaf(leaf_key)
+    }
+    fn insert_branch(&mut self, branch_key: BranchKey, branch: BranchNode) -> Result<()> {
+        self.insert_branch_count += 1;
+        self.inner.insert_branch(branch_key, branch)
+    }
+    fn insert_leaf(&mut self, leaf_key: H256, leaf: LeafNode<V>) -> Result<()> {
+        self.inner.insert_leaf(leaf_key, leaf)
+    }
+    fn remove_branch(&mut self, branch_key: &BranchKey) -> Result<()> {
+        self.remove_branch_count += 1;
+        self.inner.remove_branch(branch_key)
+    }
+    fn remove_leaf(&mut self, leaf_key: &H256) -> Result<()> {
+        self.inner.remove_leaf(leaf_key)
+    }
+}
+
+fn random_h256(rng: &mut impl Rng) -> H256 {
+    let mut buf = [0u8; 32];
+    rng.fill(&mut buf);
+    buf.into()
+}
+
+fn bench(c: &mut Criterion) {
+    c.bench_function("dense store insert_branch/remove_branch calls", |b| {
+        b.iter(|| {
+            let mut rng = thread_rng();
+            let mut smt = SMT::default();
+            for _ in 0..LEAVES_COUNT {
+                smt.update(random_h256(&mut rng), random_h256(&mut rng)).unwrap();
+            }
+            let store = smt.store();
+            println!(
+                "dense: {} leaves -> {} insert_branch, {} remove_branch, {} stored branches",
+                LEAVES_COUNT,
+                store.insert_branch_count,
+                store.remove_branch_count,
+                store.inner.branches_map().len(),
+            );
+        });
+    });
+
+    c.bench_function("compressed store node count", |b| {
+        b.iter(|| {
+            let mut rng = thread_rng();
+            let mut smt = CompressedSMT::default();
+            for _ in 0..LEAVES_COUNT {
+                smt.update(random_h256(&mut rng), random_h256(&mut rng)).unwrap();
+            }
+            println!(
+                "compressed: {} leaves -> {} stored nodes",
+                LEAVES_COUNT,
+                smt.node_count(),
+            );
+        });
+    });
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = bench
+);
+criterion_main!(benches);
@@ -0,0 +1,31 @@
+//! First-class Blake3 hasher, gated behind the `blake3` feature.
+//!
+//! `Blake3Hasher` wraps `blake3::Hasher` directly (rather than going through
+//! [`crate::digest_hasher::DigestHasher`]) so it's available without also pulling in
+//! the `digest` feature, and drops straight into `SparseMerkleTree<Blake3Hasher, _, _>`
+//! and the `MerkleProof`/`CompiledMerkleProof` `compute_root`/`verify`/`compile` flows.
+//! Blake3 is substantially faster than Blake2b for the large number of internal node
+//! hashings a deep tree performs, so this gives performance-sensitive users a drop-in
+//! swap without writing their own `Hasher`.
+
+use crate::{default_store::DefaultStore, traits::Hasher, SparseMerkleTree, H256};
+
+#[derive(Default)]
+pub struct Blake3Hasher(blake3::Hasher);
+
+impl Hasher for Blake3Hasher {
+    fn write_h256(&mut self, h: &H256) {
+        self.0.update(h.as_slice());
+    }
+    fn write_byte(&mut self, b: u8) {
+        self.0.update(&[b]);
+    }
+    fn finish(self) -> H256 {
+        (*self.0.finalize().as_bytes()).into()
+    }
+}
+
+/// A `SparseMerkleTree` over `H256` values backed by `DefaultStore` and
+/// `Blake3Hasher`, the Blake3 counterpart of `tests::c_smt::CkbSMT`'s Blake2b one,
+/// for callers who just want a ready-to-use tree type.
+pub type Blake3Smt = SparseMerkleTree<Blake3Hasher, H256, DefaultStore<H256>>;
@@ -0,0 +1,408 @@
+//! A path-compressed (Patricia/trie-style) alternative to [`DefaultStore`].
+//!
+//! [`DefaultStore`] keeps one `BranchNode` per height along every leaf's path, so a
+//! tree with `k` leaves near the bottom of the key space stores close to `k * 256`
+//! branch nodes. [`CompressedSparseMerkleTree`] instead only stores a node where the
+//! tree actually forks (or borders a leaf): a chain of single-child branches between
+//! two forks is never materialized, so storage stays `O(k)`.
+//!
+//! This turns `update`/`get`/`merkle_proof` from the dense tree's O(256) branch
+//! touches per call into O(depth): `insert_ptr`/`remove_ptr` only recurse through
+//! existing forks (at most `k - 1` of them for `k` leaves) plus one new fork for the
+//! inserted/removed key, never the full 256-height chain.
+//!
+//! Because the dense tree's own per-height `Store::insert_branch` calls don't carry
+//! enough information to reconstruct this compression (each call only sees one height
+//! in isolation), this type does not implement `Store<V>` and isn't plugged into
+//! `SparseMerkleTree` as a backend. It keeps its own pointer-based node map and
+//! exposes the same `get`/`update`/`root`/`merkle_proof` surface directly. The root
+//! and proofs it produces are computed by folding every skipped height back in through
+//! `merge`, so they're byte-for-byte identical to what a `DefaultStore`-backed tree
+//! over the same keys would produce.
+//!
+//! [`DefaultStore`]: crate::default_store::DefaultStore
+
+use crate::{
+    default_store::Map,
+    error::{Error, Result},
+    merge::merge,
+    merkle_proof::MerkleProof,
+    traits::{Hasher, Leaf, Store, Value},
+    tree::{BranchKey, BranchNode, LeafNode, SparseMerkleTree},
+    vec::Vec,
+    H256,
+};
+use core::marker::PhantomData;
+
+/// A pointer to whatever lives below a branch's child slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChildPtr {
+    Empty,
+    Leaf(H256),
+    Branch(BranchKey),
+}
+
+/// A stored fork: its two children, already folded up to `height - 1` (i.e. carrying
+/// exactly the inputs a dense `BranchNode` at this height would carry).
+#[derive(Debug, Clone)]
+struct CompressedBranch {
+    left: ChildPtr,
+    right: ChildPtr,
+    left_hash: H256,
+    right_hash: H256,
+}
+
+/// fold a subtree's hash, observed at `from_height` (`None` meaning the raw leaf hash,
+/// below height 0), up through `to_height` inclusive, merging with an implicit zero
+/// sibling at every height the compression skipped.
+fn fold_to<H: Hasher + Default>(
+    key: &H256,
+    from_height: Option<u8>,
+    from_hash: H256,
+    to_height: u8,
+) -> H256 {
+    let mut current = from_hash;
+    let start = from_height.map_or(0, |h| h + 1);
+    for height in start..=to_height {
+        let parent_key = key.parent_path(height);
+        let (left, right) = if key.is_right(height) {
+            (H256::zero(), current)
+        } else {
+            (current, H256::zero())
+        };
+        current = merge::<H>(height, &parent_key, &left, &right);
+    }
+    current
+}
+
+pub struct CompressedSparseMerkleTree<H, V> {
+    branches: Map<BranchKey, CompressedBranch>,
+    leaves: Map<H256, LeafNode<V>>,
+    root_ptr: ChildPtr,
+    root: H256,
+    phantom: PhantomData<H>,
+}
+
+impl<H, V> Default for CompressedSparseMerkleTree<H, V> {
+    fn default() -> Self {
+        CompressedSparseMerkleTree {
+            branches: Map::default(),
+            leaves: Map::default(),
+            root_ptr: ChildPtr::Empty,
+            root: H256::zero(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<H: Hasher + Default, V: Value + Leaf + Clone> CompressedSparseMerkleTree<H, V> {
+    pub fn root(&self) -> &H256 {
+        &self.root
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_zero()
+    }
+
+    /// number of stored branch + leaf nodes, the quantity this store compresses
+    pub fn node_count(&self) -> usize {
+        self.branches.len() + self.leaves.len()
+    }
+
+    pub fn get(&self, key: &H256) -> Result<V> {
+        let mut ptr = self.root_ptr;
+        loop {
+            match ptr {
+                ChildPtr::Empty => return Ok(V::zero()),
+                ChildPtr::Leaf(leaf_key) => {
+                    let leaf = self.leaves.get(&leaf_key).ok_or(Error::MissingLeaf(leaf_key))?;
+                    return if leaf.key() == *key {
+                        leaf.clone().into_value()
+                    } else {
+                        Ok(V::zero())
+                    };
+                }
+                ChildPtr::Branch(bk) => {
+                    let branch = self
+                        .branches
+                        .get(&bk)
+                        .ok_or_else(|| Error::MissingBranch(bk.node_key()))?;
+                    ptr = if key.is_right(bk.height()) {
+                        branch.right
+                    } else {
+                        branch.left
+                    };
+                }
+            }
+        }
+    }
+
+    pub fn update(&mut self, key: H256, value: V) -> Result<&H256> {
+        if value.leaf_hash::<H>().is_zero() {
+            self.leaves.remove(&key);
+            self.root_ptr = self.remove_ptr(self.root_ptr, key)?;
+        } else {
+            self.leaves.insert(key, LeafNode::Live { key, value });
+            self.root_ptr = self.insert_ptr(self.root_ptr, key)?;
+        }
+        self.root = self.ptr_root_hash()?;
+        Ok(&self.root)
+    }
+
+    /// Build a standard `MerkleProof` covering `keys`, by replaying this tree through
+    /// a read-only `Store` view and reusing the dense proof algorithm.
+    pub fn merkle_proof(&self, keys: Vec<H256>) -> Result<MerkleProof> {
+        let view = CompressedStoreView { tree: self };
+        SparseMerkleTree::<H, V, _>::new(self.root, view).merkle_proof(keys)
+    }
+
+    fn ptr_natural(&self, ptr: ChildPtr) -> Result<(H256, Option<u8>, H256)> {
+        match ptr {
+            ChildPtr::Empty => Ok((H256::zero(), None, H256::zero())),
+            ChildPtr::Leaf(key) => {
+                let leaf = self.leaves.get(&key).ok_or(Error::MissingLeaf(key))?;
+                let hash = leaf.hash::<H>();
+                Ok((hash, None, key))
+            }
+            ChildPtr::Branch(bk) => {
+                let branch = self
+                    .branches
+                    .get(&bk)
+                    .ok_or_else(|| Error::MissingBranch(bk.node_key()))?;
+                let hash = merge::<H>(bk.height(), &bk.node_key(), &branch.left_hash, &branch.right_hash);
+                Ok((hash, Some(bk.height()), bk.node_key()))
+            }
+        }
+    }
+
+    /// the hash `ptr`'s subtree contributes as a child of a branch at `parent_height`
+    fn ptr_hash_below(&self, ptr: ChildPtr, parent_height: u8) -> Result<H256> {
+        if ptr == ChildPtr::Empty {
+            return Ok(H256::zero());
+        }
+        let (hash, from_height, rep_key) = self.ptr_natural(ptr)?;
+        match parent_height.checked_sub(1) {
+            None => Ok(hash),
+            Some(to_height) => Ok(fold_to::<H>(&rep_key, from_height, hash, to_height)),
+        }
+    }
+
+    fn ptr_root_hash(&self) -> Result<H256> {
+        if self.root_ptr == ChildPtr::Empty {
+            return Ok(H256::zero());
+        }
+        let (hash, from_height, rep_key) = self.ptr_natural(self.root_ptr)?;
+        Ok(fold_to::<H>(&rep_key, from_height, hash, core::u8::MAX))
+    }
+
+    /// insert `key` (already present in `self.leaves`) under `ptr`, returning the new
+    /// pointer to the (possibly rebuilt) subtree
+    fn insert_ptr(&mut self, ptr: ChildPtr, key: H256) -> Result<ChildPtr> {
+        match ptr {
+            ChildPtr::Empty => Ok(ChildPtr::Leaf(key)),
+            ChildPtr::Leaf(existing_key) => {
+                if existing_key == key {
+                    return Ok(ChildPtr::Leaf(key));
+                }
+                let height = existing_key.fork_height(&key);
+                let existing_hash = {
+                    let leaf = self.leaves.get(&existing_key).ok_or(Error::MissingLeaf(existing_key))?;
+                    leaf.hash::<H>()
+                };
+                let new_hash = {
+                    let leaf = self.leaves.get(&key).ok_or(Error::MissingLeaf(key))?;
+                    leaf.hash::<H>()
+                };
+                let existing_folded = self.ptr_hash_below_raw(existing_key, existing_hash, height)?;
+                let new_folded = self.ptr_hash_below_raw(key, new_hash, height)?;
+                let (left, right, left_hash, right_hash) = if key.is_right(height) {
+                    (ChildPtr::Leaf(existing_key), ChildPtr::Leaf(key), existing_folded, new_folded)
+                } else {
+                    (ChildPtr::Leaf(key), ChildPtr::Leaf(existing_key), new_folded, existing_folded)
+                };
+                let branch_key = BranchKey::new(height, key.parent_path(height));
+                self.branches.insert(
+                    branch_key,
+                    CompressedBranch {
+                        left,
+                        right,
+                        left_hash,
+                        right_hash,
+                    },
+                );
+                Ok(ChildPtr::Branch(branch_key))
+            }
+            ChildPtr::Branch(bk) => {
+                let branch_height = bk.height();
+                let fork_height = bk.node_key().fork_height(&key);
+                if fork_height > branch_height {
+                    // `key` diverges from this whole subtree above it: insert a new
+                    // fork that takes the existing branch as one of its children
+                    let new_hash = {
+                        let leaf = self.leaves.get(&key).ok_or(Error::MissingLeaf(key))?;
+                        leaf.hash::<H>()
+                    };
+                    let new_folded = self.ptr_hash_below_raw(key, new_hash, fork_height)?;
+                    let existing_folded = self.ptr_hash_below(ChildPtr::Branch(bk), fork_height)?;
+                    let (left, right, left_hash, right_hash) = if key.is_right(fork_height) {
+                        (ChildPtr::Branch(bk), ChildPtr::Leaf(key), existing_folded, new_folded)
+                    } else {
+                        (ChildPtr::Leaf(key), ChildPtr::Branch(bk), new_folded, existing_folded)
+                    };
+                    let new_branch_key = BranchKey::new(fork_height, key.parent_path(fork_height));
+                    self.branches.insert(
+                        new_branch_key,
+                        CompressedBranch {
+                            left,
+                            right,
+                            left_hash,
+                            right_hash,
+                        },
+                    );
+                    Ok(ChildPtr::Branch(new_branch_key))
+                } else {
+                    // `key` belongs under this branch: recurse, then refresh the
+                    // folded hash of whichever side changed
+                    let mut branch = self
+                        .branches
+                        .get(&bk)
+                        .ok_or_else(|| Error::MissingBranch(bk.node_key()))?
+                        .clone();
+                    if key.is_right(branch_height) {
+                        branch.right = self.insert_ptr(branch.right, key)?;
+                        branch.right_hash = self.ptr_hash_below(branch.right, branch_height)?;
+                    } else {
+                        branch.left = self.insert_ptr(branch.left, key)?;
+                        branch.left_hash = self.ptr_hash_below(branch.left, branch_height)?;
+                    }
+                    self.branches.insert(bk, branch);
+                    Ok(ChildPtr::Branch(bk))
+                }
+            }
+        }
+    }
+
+    /// fold a freshly-hashed leaf's value up to just below `to_height`
+    fn ptr_hash_below_raw(&self, key: H256, leaf_hash: H256, to_height: u8) -> Result<H256> {
+        match to_height.checked_sub(1) {
+            None => Ok(leaf_hash),
+            Some(to_height) => Ok(fold_to::<H>(&key, None, leaf_hash, to_height)),
+        }
+    }
+
+    fn remove_ptr(&mut self, ptr: ChildPtr, key: H256) -> Result<ChildPtr> {
+        match ptr {
+            ChildPtr::Empty => Ok(ChildPtr::Empty),
+            ChildPtr::Leaf(existing_key) => {
+                if existing_key == key {
+                    Ok(ChildPtr::Empty)
+                } else {
+                    Ok(ptr)
+                }
+            }
+            ChildPtr::Branch(bk) => {
+                let branch_height = bk.height();
+                let mut branch = self
+                    .branches
+                    .get(&bk)
+                    .ok_or_else(|| Error::MissingBranch(bk.node_key()))?
+                    .clone();
+                if key.is_right(branch_height) {
+                    branch.right = self.remove_ptr(branch.right, key)?;
+                } else {
+                    branch.left = self.remove_ptr(branch.left, key)?;
+                }
+                match (branch.left, branch.right) {
+                    (ChildPtr::Empty, other) | (other, ChildPtr::Empty) => {
+                        self.branches.remove(&bk);
+                        Ok(other)
+                    }
+                    _ => {
+                        branch.left_hash = self.ptr_hash_below(branch.left, branch_height)?;
+                        branch.right_hash = self.ptr_hash_below(branch.right, branch_height)?;
+                        self.branches.insert(bk, branch);
+                        Ok(ChildPtr::Branch(bk))
+                    }
+                }
+            }
+        }
+    }
+
+    /// answer a dense-style branch query at `(height, node_key)` by walking down from
+    /// the root and expanding the compressed edge straddling that height, if any
+    fn virtual_branch(&self, height: u8, node_key: H256) -> Result<Option<BranchNode>> {
+        let mut ptr = self.root_ptr;
+        loop {
+            match ptr {
+                ChildPtr::Empty => return Ok(None),
+                ChildPtr::Branch(bk) if bk.height() > height => {
+                    let branch = self
+                        .branches
+                        .get(&bk)
+                        .ok_or_else(|| Error::MissingBranch(bk.node_key()))?;
+                    ptr = if node_key.is_right(bk.height()) {
+                        branch.right
+                    } else {
+                        branch.left
+                    };
+                }
+                ChildPtr::Branch(bk) if bk.height() == height => {
+                    if bk.node_key() != node_key {
+                        // `node_key`'s path leaves the stored tree above `height`
+                        return Ok(None);
+                    }
+                    let branch = self
+                        .branches
+                        .get(&bk)
+                        .ok_or_else(|| Error::MissingBranch(bk.node_key()))?;
+                    return Ok(Some(BranchNode::new(branch.left_hash, branch.right_hash)));
+                }
+                _ => {
+                    // a leaf, or a fork strictly below `height`: the whole subtree
+                    // collapses into one folded value at this height
+                    let (hash, from_height, rep_key) = self.ptr_natural(ptr)?;
+                    if rep_key.parent_path(height) != node_key {
+                        // `node_key`'s path leaves the stored tree above `height`
+                        return Ok(None);
+                    }
+                    let folded = match height.checked_sub(1) {
+                        None => hash,
+                        Some(to_height) => fold_to::<H>(&rep_key, from_height, hash, to_height),
+                    };
+                    return Ok(Some(if rep_key.is_right(height) {
+                        BranchNode::new(H256::zero(), folded)
+                    } else {
+                        BranchNode::new(folded, H256::zero())
+                    }));
+                }
+            }
+        }
+    }
+}
+
+/// read-only `Store` view used only to reuse `SparseMerkleTree::merkle_proof`; the
+/// mutating methods are never called during proof generation
+struct CompressedStoreView<'a, H, V> {
+    tree: &'a CompressedSparseMerkleTree<H, V>,
+}
+
+impl<'a, H: Hasher + Default, V: Value + Leaf + Clone> Store<V> for CompressedStoreView<'a, H, V> {
+    fn get_branch(&self, branch_key: &BranchKey) -> Result<Option<BranchNode>> {
+        self.tree.virtual_branch(branch_key.height(), branch_key.node_key())
+    }
+    fn get_leaf(&self, leaf_key: &H256) -> Result<Option<LeafNode<V>>> {
+        Ok(self.tree.leaves.get(leaf_key).cloned())
+    }
+    fn insert_branch(&mut self, _branch_key: BranchKey, _branch: BranchNode) -> Result<()> {
+        Ok(())
+    }
+    fn insert_leaf(&mut self, _leaf_key: H256, _leaf: LeafNode<V>) -> Result<()> {
+        Ok(())
+    }
+    fn remove_branch(&mut self, _branch_key: &BranchKey) -> Result<()> {
+        Ok(())
+    }
+    fn remove_leaf(&mut self, _leaf_key: &H256) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+//! A reusable [`Store`] decorator that counts backend accesses, generalizing the
+//! ad-hoc `CountingStore` in `benches/store_counter_benchmark.rs` (which only wraps
+//! [`DefaultStore`](crate::default_store::DefaultStore) and tracks two of the six
+//! `Store` methods) into one that wraps any inner store and counts every method.
+//!
+//! Counters are [`AtomicUsize`], so the `&self` read methods (`get_branch`/`get_leaf`)
+//! can bump them too, and every counter can be read through a shared reference (e.g.
+//! `tree.store().counters()`).
+
+use crate::{
+    error::Result,
+    traits::Store,
+    tree::{BranchKey, BranchNode, LeafNode},
+    H256,
+};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Per-operation invocation counts recorded by a [`CountingStore`].
+#[derive(Debug, Default)]
+pub struct StoreCounters {
+    pub get_branch: AtomicUsize,
+    pub get_leaf: AtomicUsize,
+    pub insert_branch: AtomicUsize,
+    pub insert_leaf: AtomicUsize,
+    pub remove_branch: AtomicUsize,
+    pub remove_leaf: AtomicUsize,
+}
+
+impl StoreCounters {
+    fn bump(counter: &AtomicUsize) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Wraps an inner `S: Store<V>`, forwarding every call and counting how many times
+/// each was invoked.
+#[derive(Default)]
+pub struct CountingStore<S> {
+    inner: S,
+    counters: StoreCounters,
+}
+
+impl<S> CountingStore<S> {
+    /// Wrap `inner`, starting every counter at zero.
+    pub fn new(inner: S) -> Self {
+        CountingStore {
+            inner,
+            counters: StoreCounters::default(),
+        }
+    }
+
+    /// The wrapped store.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Unwrap, discarding the counters.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// The recorded invocation counts.
+    pub fn counters(&self) -> &StoreCounters {
+        &self.counters
+    }
+}
+
+impl<V, S: Store<V>> Store<V> for CountingStore<S> {
+    fn get_branch(&self, branch_key: &BranchKey) -> Result<Option<BranchNode>> {
+        StoreCounters::bump(&self.counters.get_branch);
+        self.inner.get_branch(branch_key)
+    }
+    fn get_leaf(&self, leaf_key: &H256) -> Result<Option<LeafNode<V>>> {
+        StoreCounters::bump(&self.counters.get_leaf);
+        self.inner.get_leaf(leaf_key)
+    }
+    fn insert_branch(&mut self, branch_key: BranchKey, branch: BranchNode) -> Result<()> {
+        StoreCounters::bump(&self.counters.insert_branch);
+        self.inner.insert_branch(branch_key, branch)
+    }
+    fn insert_leaf(&mut self, leaf_key: H256, leaf: LeafNode<V>) -> Result<()> {
+        StoreCounters::bump(&self.counters.insert_leaf);
+        self.inner.insert_leaf(leaf_key, leaf)
+    }
+    fn remove_branch(&mut self, branch_key: &BranchKey) -> Result<()> {
+        StoreCounters::bump(&self.counters.remove_branch);
+        self.inner.remove_branch(branch_key)
+    }
+    fn remove_leaf(&mut self, leaf_key: &H256) -> Result<()> {
+        StoreCounters::bump(&self.counters.remove_leaf);
+        self.inner.remove_leaf(leaf_key)
+    }
+}
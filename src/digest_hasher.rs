@@ -0,0 +1,34 @@
+//! Bridges any RustCrypto [`digest::Digest`] (SHA-256, Keccak-256, Blake3, ...) into
+//! this crate's [`Hasher`], the way the `lsmtree` and `atms` trees are generic over
+//! `Digest` rather than hand-rolling one hasher type per algorithm.
+
+use crate::{traits::Hasher, H256};
+use digest::Digest;
+
+/// Adapts a RustCrypto [`Digest`] into this crate's [`Hasher`].
+///
+/// `write_h256`/`write_byte` feed straight into `Digest::update`; `finish` takes the
+/// first 32 bytes of the digest's fixed output as the `H256`. Pick a `D` whose output
+/// is at least 32 bytes (SHA-256, Keccak-256, Blake3, ...); a shorter digest panics in
+/// `finish` rather than silently padding.
+#[derive(Default)]
+pub struct DigestHasher<D>(D);
+
+impl<D: Digest> Hasher for DigestHasher<D> {
+    fn write_h256(&mut self, h: &H256) {
+        self.0.update(h.as_slice());
+    }
+    fn write_byte(&mut self, b: u8) {
+        self.0.update([b]);
+    }
+    fn finish(self) -> H256 {
+        let output = self.0.finalize();
+        assert!(
+            output.len() >= 32,
+            "DigestHasher requires a digest with a >= 32-byte output"
+        );
+        let mut buf = [0u8; 32];
+        buf.copy_from_slice(&output[..32]);
+        buf.into()
+    }
+}
@@ -15,6 +15,7 @@ pub enum Error {
     NonSiblings,
     InvalidCode(u8),
     NonMergableRange,
+    Sealed,
 }
 
 impl core::fmt::Display for Error {
@@ -57,6 +58,9 @@ impl core::fmt::Display for Error {
             Error::NonMergableRange => {
                 write!(f, "Ranges can not be merged")?;
             }
+            Error::Sealed => {
+                write!(f, "Leaf is sealed, value is no longer readable or updatable")?;
+            }
         }
         Ok(())
     }
@@ -1,4 +1,6 @@
+use crate::vec::Vec;
 use core::cmp::Ordering;
+use core::convert::TryFrom;
 #[derive(Eq, PartialEq, Debug, Default, Hash, Clone, Copy)]
 pub struct H256([u8; 32]);
 
@@ -131,3 +133,21 @@ impl Into<[u8; 32]> for H256 {
         self.0
     }
 }
+
+impl AsRef<[u8]> for H256 {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl TryFrom<Vec<u8>> for H256 {
+    /// the rejected vec, handed back unchanged
+    type Error = Vec<u8>;
+
+    /// succeeds only for an exactly 32-byte vec; a wrong-length vec is handed back
+    /// rather than silently truncated/zero-padded or panicking, so a persistent
+    /// `Store` decoding a corrupted record can turn this into `Error::Store` instead.
+    fn try_from(v: Vec<u8>) -> Result<Self, Self::Error> {
+        <[u8; 32]>::try_from(v.as_slice()).map(H256).map_err(|_| v)
+    }
+}
@@ -0,0 +1,282 @@
+//! A backend-agnostic generalization of [`RocksDbStore`](crate::rocksdb_store::RocksDbStore):
+//! instead of hardcoding RocksDB, [`KvStore<B, V>`] is generic over any small
+//! [`KvBackend`], the way merkletree-rs lets callers choose a filesystem or
+//! in-memory database at construction time. Wiring in sled (or any other embedded
+//! key-value database) only requires implementing [`KvBackend`]/[`KvBatch`] for it;
+//! branch/leaf encoding and the write-batch-then-commit flow are exactly
+//! `RocksDbStore`'s, just parameterized over the backend.
+//!
+//! A [`KvBackend`] impl for `rocksdb::DB` is provided behind the `rocksdb` feature as
+//! a worked example: [`open_rocksdb`] opens one and returns a ready-to-use
+//! `KvStore<DB, V>`.
+
+use crate::{
+    error::{Error, Result},
+    string::String,
+    traits::{Hasher, Leaf, Store, Value},
+    tree::{BranchKey, BranchNode, LeafNode, SparseMerkleTree},
+    vec::Vec,
+    H256,
+};
+use core::convert::TryFrom;
+use core::marker::PhantomData;
+
+const ROOT_KEY: &[u8] = b"root";
+const BRANCH_PREFIX: u8 = 0;
+const LEAF_PREFIX: u8 = 1;
+
+fn branch_store_key(branch_key: &BranchKey) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(34);
+    buf.push(BRANCH_PREFIX);
+    buf.push(branch_key.height());
+    buf.extend_from_slice(branch_key.node_key().as_slice());
+    buf
+}
+
+fn leaf_store_key(leaf_key: &H256) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(33);
+    buf.push(LEAF_PREFIX);
+    buf.extend_from_slice(leaf_key.as_slice());
+    buf
+}
+
+fn encode_branch(branch: &BranchNode) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(64);
+    buf.extend_from_slice(branch.left().as_slice());
+    buf.extend_from_slice(branch.right().as_slice());
+    buf
+}
+
+fn decode_branch(bytes: &[u8]) -> Result<BranchNode> {
+    if bytes.len() != 64 {
+        return Err(Error::Store(String::from("corrupted branch record")));
+    }
+    let mut left = [0u8; 32];
+    let mut right = [0u8; 32];
+    left.copy_from_slice(&bytes[..32]);
+    right.copy_from_slice(&bytes[32..]);
+    Ok(BranchNode::new(left.into(), right.into()))
+}
+
+fn encode_leaf<V: AsRef<[u8]>>(leaf: &LeafNode<V>) -> Vec<u8> {
+    match leaf {
+        LeafNode::Live { value, .. } => {
+            let mut buf = Vec::with_capacity(1 + value.as_ref().len());
+            buf.push(0);
+            buf.extend_from_slice(value.as_ref());
+            buf
+        }
+        LeafNode::Sealed { hash, .. } => {
+            let mut buf = Vec::with_capacity(33);
+            buf.push(1);
+            buf.extend_from_slice(hash.as_slice());
+            buf
+        }
+    }
+}
+
+fn decode_leaf<V: TryFrom<Vec<u8>>>(key: H256, bytes: &[u8]) -> Result<LeafNode<V>> {
+    match bytes.split_first() {
+        Some((0, value)) => Ok(LeafNode::Live {
+            key,
+            value: V::try_from(value.to_vec())
+                .map_err(|_| Error::Store(String::from("corrupted leaf record")))?,
+        }),
+        Some((1, hash)) if hash.len() == 32 => {
+            let mut buf = [0u8; 32];
+            buf.copy_from_slice(hash);
+            Ok(LeafNode::Sealed {
+                key,
+                hash: buf.into(),
+            })
+        }
+        _ => Err(Error::Store(String::from("corrupted leaf record"))),
+    }
+}
+
+/// An accumulating batch of writes for a [`KvBackend`], flushed atomically by
+/// [`KvBackend::write_batch`].
+pub trait KvBatch: Default {
+    fn put(&mut self, key: &[u8], value: &[u8]);
+    fn delete(&mut self, key: &[u8]);
+}
+
+/// A minimal key-value database capable of backing a [`KvStore`]. Implement this
+/// (and [`KvBatch`] for the associated batch type) to plug in a new embedded
+/// database; `KvStore` handles everything else (key derivation, branch/leaf
+/// encoding, root bookkeeping).
+pub trait KvBackend {
+    type Batch: KvBatch;
+
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>>;
+
+    /// Flush `batch` as a single atomic write.
+    fn write_batch(&self, batch: Self::Batch) -> Result<()>;
+}
+
+/// A persistent [`Store`] over any [`KvBackend`]. Writes issued through
+/// `insert_branch`/`insert_leaf`/`remove_branch`/`remove_leaf` accumulate in an
+/// in-memory batch; [`KvStore::commit_root`] flushes that batch together with the
+/// tree's new root as one atomic write, the same way
+/// [`RocksDbStore::commit_root`](crate::rocksdb_store::RocksDbStore::commit_root)
+/// does.
+pub struct KvStore<B: KvBackend, V> {
+    backend: B,
+    batch: B::Batch,
+    phantom: PhantomData<V>,
+}
+
+impl<B: KvBackend, V> KvStore<B, V> {
+    /// Wrap an already-open backend.
+    pub fn new(backend: B) -> Self {
+        KvStore {
+            backend,
+            batch: B::Batch::default(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// The backing database.
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// The root as of the last `commit_root`, or `H256::zero()` for a fresh database.
+    pub fn root(&self) -> Result<H256> {
+        match self.backend.get(ROOT_KEY)? {
+            None => Ok(H256::zero()),
+            Some(bytes) if bytes.len() == 32 => {
+                let mut buf = [0u8; 32];
+                buf.copy_from_slice(&bytes);
+                Ok(buf.into())
+            }
+            Some(_) => Err(Error::Store(String::from("corrupted root record"))),
+        }
+    }
+
+    /// Flush every branch/leaf write buffered since the last `commit_root`, together
+    /// with the new `root`, as a single atomic write.
+    pub fn commit_root(&mut self, root: H256) -> Result<()> {
+        let mut batch = core::mem::take(&mut self.batch);
+        batch.put(ROOT_KEY, root.as_slice());
+        self.backend.write_batch(batch)
+    }
+}
+
+impl<B: KvBackend, V: AsRef<[u8]> + TryFrom<Vec<u8>>> Store<V> for KvStore<B, V> {
+    fn get_branch(&self, branch_key: &BranchKey) -> Result<Option<BranchNode>> {
+        match self.backend.get(&branch_store_key(branch_key))? {
+            Some(bytes) => Ok(Some(decode_branch(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+    fn get_leaf(&self, leaf_key: &H256) -> Result<Option<LeafNode<V>>> {
+        match self.backend.get(&leaf_store_key(leaf_key))? {
+            Some(bytes) => Ok(Some(decode_leaf(*leaf_key, &bytes)?)),
+            None => Ok(None),
+        }
+    }
+    fn insert_branch(&mut self, branch_key: BranchKey, branch: BranchNode) -> Result<()> {
+        self.batch
+            .put(&branch_store_key(&branch_key), &encode_branch(&branch));
+        Ok(())
+    }
+    fn insert_leaf(&mut self, leaf_key: H256, leaf: LeafNode<V>) -> Result<()> {
+        self.batch
+            .put(&leaf_store_key(&leaf_key), &encode_leaf(&leaf));
+        Ok(())
+    }
+    fn remove_branch(&mut self, branch_key: &BranchKey) -> Result<()> {
+        self.batch.delete(&branch_store_key(branch_key));
+        Ok(())
+    }
+    fn remove_leaf(&mut self, leaf_key: &H256) -> Result<()> {
+        self.batch.delete(&leaf_store_key(leaf_key));
+        Ok(())
+    }
+}
+
+/// Rebuild the tree sitting on top of an already-open `backend`, recovering its root
+/// from the dedicated root key (`H256::zero()` for a fresh database).
+pub fn open_tree<H, V, B>(backend: B) -> Result<SparseMerkleTree<H, V, KvStore<B, V>>>
+where
+    H: Hasher + Default,
+    V: Value + Leaf + AsRef<[u8]> + TryFrom<Vec<u8>>,
+    B: KvBackend,
+{
+    let store = KvStore::new(backend);
+    let root = store.root()?;
+    Ok(SparseMerkleTree::new(root, store))
+}
+
+/// Apply every `(key, value)` pair in `updates` to `tree`, then flush all the
+/// branches and leaves they touched together with the new root as one atomic write
+/// batch, instead of one write per touched node.
+///
+/// Goes through `SparseMerkleTree::update_all` rather than one `update` per pair:
+/// `KvStore`'s `get_branch`/`get_leaf` only ever read the backend, not the
+/// still-unflushed writes sitting in `batch`, so a loop of individual `update` calls
+/// would have each pair's `recompute_root` read stale (pre-batch) branch state for
+/// every earlier pair in the same call and silently drop them from the root.
+/// `update_all` only tracks dirty nodes in memory, so it never hits that gap.
+pub fn update_all<H, V, B>(
+    tree: &mut SparseMerkleTree<H, V, KvStore<B, V>>,
+    updates: Vec<(H256, V)>,
+) -> Result<H256>
+where
+    H: Hasher + Default,
+    V: Value + Leaf + AsRef<[u8]> + TryFrom<Vec<u8>>,
+    B: KvBackend,
+{
+    tree.update_all(updates)?;
+    let root = *tree.root();
+    tree.store_mut().commit_root(root)?;
+    Ok(root)
+}
+
+#[cfg(feature = "rocksdb")]
+mod rocksdb_backend {
+    use super::{KvBackend, KvBatch, KvStore};
+    use crate::{
+        error::{Error, Result},
+        string::String,
+    };
+    use rocksdb::{WriteBatch, DB};
+    use std::path::Path;
+
+    fn to_store_err<E: core::fmt::Display>(err: E) -> Error {
+        Error::Store(String::from(format!("{}", err)))
+    }
+
+    impl KvBatch for WriteBatch {
+        fn put(&mut self, key: &[u8], value: &[u8]) {
+            WriteBatch::put(self, key, value);
+        }
+        fn delete(&mut self, key: &[u8]) {
+            WriteBatch::delete(self, key);
+        }
+    }
+
+    impl KvBackend for DB {
+        type Batch = WriteBatch;
+
+        fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+            DB::get(self, key).map_err(to_store_err)
+        }
+        fn write_batch(&self, batch: WriteBatch) -> Result<()> {
+            DB::write(self, batch).map_err(to_store_err)
+        }
+    }
+
+    /// Open (or create) the RocksDB database at `path` as a [`KvStore<DB, V>`],
+    /// showing that this module's generic store can stand in for the concrete
+    /// [`RocksDbStore`](crate::rocksdb_store::RocksDbStore); any other embedded
+    /// key-value database just needs its own `KvBackend` impl.
+    pub fn open_rocksdb<V>(path: impl AsRef<Path>) -> Result<KvStore<DB, V>> {
+        let db = DB::open_default(path).map_err(to_store_err)?;
+        Ok(KvStore::new(db))
+    }
+}
+
+#[cfg(feature = "rocksdb")]
+pub use rocksdb_backend::open_rocksdb;
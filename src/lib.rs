@@ -6,7 +6,7 @@
 //! use sparse_merkle_tree::{
 //!     blake2b::Blake2bHasher, default_store::DefaultStore,
 //!     error::Error, MerkleProof,
-//!     SparseMerkleTree, traits::Value, H256
+//!     SparseMerkleTree, traits::{Leaf, Value}, H256
 //! };
 //! use blake2b_rs::{Blake2b, Blake2bBuilder};
 //!
@@ -31,6 +31,11 @@
 //!        Default::default()
 //!    }
 //! }
+//! impl Leaf for Word {
+//!    fn bytes(&self) -> &[u8] {
+//!        self.0.as_bytes()
+//!    }
+//! }
 //!
 //! // helper function
 //! fn new_blake2b() -> Blake2b {
@@ -63,18 +68,34 @@
 
 #[cfg(feature = "blake2b")]
 pub mod blake2b;
+#[cfg(feature = "blake3")]
+pub mod blake3;
+#[cfg(feature = "trie")]
+pub mod compressed_store;
+pub mod counting_store;
 pub mod default_store;
+#[cfg(feature = "digest")]
+pub mod digest_hasher;
 pub mod error;
 pub mod h256;
+pub mod kv_store;
 pub mod merge;
 pub mod merkle_proof;
+pub mod namespaced_smt;
+#[cfg(feature = "poseidon")]
+pub mod poseidon;
+pub mod range_proof;
+#[cfg(feature = "rocksdb")]
+pub mod rocksdb_store;
 #[cfg(test)]
 mod tests;
 pub mod traits;
 pub mod tree;
 
 pub use h256::H256;
-pub use merkle_proof::{CompiledMerkleProof, MerkleProof};
+pub use merkle_proof::{verify_compiled, CompiledMerkleProof, MerkleProof, ProofCost};
+pub use namespaced_smt::NamespacedSmt;
+pub use range_proof::{KeyRange, RangeProof};
 pub use tree::SparseMerkleTree;
 
 /// Expected path size: log2(256) * 2, used for hint vector capacity
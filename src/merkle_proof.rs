@@ -6,15 +6,24 @@ use crate::{
     H256,
 };
 
+/// version byte stamped on the canonical `to_bytes`/`from_bytes` wire format
+const PROOF_VERSION: u8 = 1;
+
+/// `DEPTH` must match the `SparseMerkleTree<H, V, S, DEPTH>` the proof was produced
+/// from (defaulting to the full 256-bit key space): it bounds how many heights
+/// `compute_root`/`cost` fold through, the same way it bounds `update`/`merkle_proof`
+/// on the tree side. A proof folded with the wrong `DEPTH` reconstructs a different
+/// root rather than erroring, since folding one more or one fewer height just hashes
+/// to a different, equally well-formed-looking value.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct MerkleProof {
+pub struct MerkleProof<const DEPTH: usize = { core::u8::MAX as usize }> {
     // leaf path represented by bitmap
     leaves_path: Vec<H256>,
     // needed sibling node hash
     proof: Vec<H256>,
 }
 
-impl MerkleProof {
+impl<const DEPTH: usize> MerkleProof<DEPTH> {
     /// Create MerkleProof
     /// leaves_path: contains height of non-zero siblings
     /// proof: contains merkle path for each leaves it's height
@@ -43,8 +52,47 @@ impl MerkleProof {
         &self.proof
     }
 
+    /// Serialize into a canonical, self-describing byte layout: a 1-byte version,
+    /// a 4-byte little-endian leaf count, the `leaves_path` hashes, then the
+    /// sibling `proof` hashes. Unlike `compile()`'s flat output, this round-trips
+    /// through `from_bytes` without an out-of-band leaf count.
+    pub fn to_bytes(self) -> Vec<u8> {
+        let (leaves_path, proof) = self.take();
+        let mut data = Vec::with_capacity(5 + (leaves_path.len() + proof.len()) * 32);
+        data.push(PROOF_VERSION);
+        data.extend_from_slice(&(leaves_path.len() as u32).to_le_bytes());
+        for path in &leaves_path {
+            data.extend_from_slice(path.as_slice());
+        }
+        for sibling_node_hash in &proof {
+            data.extend_from_slice(sibling_node_hash.as_slice());
+        }
+        data
+    }
+
+    /// Parse a proof produced by `to_bytes`.
+    ///
+    /// return `Error::InvalidCode` when the version byte is unrecognized, and
+    /// `Error::CorruptedProof` when the header or body length doesn't add up.
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let (leaves_count, body) = decode_header(data)?;
+        let total = body.len() / 32;
+        let mut hash = [0u8; 32];
+        let mut leaves_path = Vec::with_capacity(leaves_count);
+        for idx in 0..leaves_count {
+            hash.copy_from_slice(&body[idx * 32..idx * 32 + 32]);
+            leaves_path.push(H256::from(hash));
+        }
+        let mut proof = Vec::with_capacity(total - leaves_count);
+        for idx in leaves_count..total {
+            hash.copy_from_slice(&body[idx * 32..idx * 32 + 32]);
+            proof.push(H256::from(hash));
+        }
+        Ok(MerkleProof::new(leaves_path, proof))
+    }
+
     /// convert merkle proof into CompiledMerkleProof
-    pub fn compile(self) -> CompiledMerkleProof {
+    pub fn compile(self) -> CompiledMerkleProof<DEPTH> {
         let (leaves_path, proof) = self.take();
         let leaves_len = leaves_path.len();
         let mut data = vec![0u8; (leaves_len + proof.len()) * 32];
@@ -86,7 +134,7 @@ impl MerkleProof {
             .map(|(path_idx, (key, value))| (path_idx, key, hash_leaf::<H>(&key, &value)))
             .collect();
         let mut next_nodes: Vec<(usize, H256, H256)> = Default::default();
-        for height in 0..=core::u8::MAX {
+        for height in 0..=(DEPTH as u8) {
             let mut key_idx = 0;
             while key_idx < current_nodes.len() {
                 let (path_idx_a, key_a, node_a) = current_nodes[key_idx];
@@ -161,13 +209,195 @@ impl MerkleProof {
         let calculated_root = self.compute_root::<H>(leaves)?;
         Ok(&calculated_root == root)
     }
+
+    /// Like `compute_root`, but leaves are `(key, Option<value>)` pairs: `None`
+    /// proves non-membership explicitly, rather than requiring the caller to know
+    /// the zero value represents an empty leaf. Since this only reconstructs a root
+    /// (it doesn't compare against a claimed one), it also lets a caller diff two
+    /// roots, or clone the same proof and call it twice with different `Option`
+    /// values per key to compute the root before and after an update from a single
+    /// membership/non-membership proof.
+    pub fn compute_root_opt<H: Hasher + Default>(
+        self,
+        leaves: Vec<(H256, Option<H256>)>,
+    ) -> Result<H256> {
+        let leaves = leaves
+            .into_iter()
+            .map(|(key, value)| (key, value.unwrap_or_else(H256::zero)))
+            .collect();
+        self.compute_root::<H>(leaves)
+    }
+
+    /// Like `verify`, but accepts `(key, Option<value>)` pairs; see `compute_root_opt`.
+    pub fn verify_opt<H: Hasher + Default>(
+        self,
+        root: &H256,
+        leaves: Vec<(H256, Option<H256>)>,
+    ) -> Result<bool> {
+        let calculated_root = self.compute_root_opt::<H>(leaves)?;
+        Ok(&calculated_root == root)
+    }
+
+    /// The hashing work a verifier of this proof must perform against `keys`,
+    /// without actually running a `Hasher`: see [`ProofCost`].
+    ///
+    /// return `Error::EmptyKeys`/`Error::IncorrectNumberOfLeaves` under the same
+    /// conditions as `compute_root`, and `Error::CorruptedProof` if `proof` doesn't
+    /// hold exactly the sibling hashes `leaves_path` calls for.
+    pub fn cost(&self, keys: Vec<H256>) -> Result<ProofCost> {
+        proof_cost::<DEPTH>(&self.leaves_path, &self.proof, keys)
+    }
+}
+
+/// The hashing work a verifier must perform to reconstruct a root from a proof and a
+/// set of keys: how many sibling hashes the proof carries, plus how many `Hasher`
+/// invocations (`hash_leaf` and `merge` calls) folding them in takes. Lets
+/// gas-budgeted verifiers (e.g. on-chain) measure and regression-test the cost of a
+/// proof produced by `merkle_proof`/`compile` without running a real `Hasher`.
+///
+/// `merge_hashes`/`leaf_hashes` count every fold step as a hash invocation, i.e. they
+/// ignore `merge`/`hash_leaf`'s own zero-value short-circuit (folding two zero
+/// siblings, or hashing a zero leaf value, returns `H256::zero()` without hashing).
+/// That makes this the exact cost for membership proofs of non-zero values, and an
+/// upper bound otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ProofCost {
+    /// sibling hashes embedded in the proof
+    pub sibling_hashes: usize,
+    /// `hash_leaf` invocations, one per proven key
+    pub leaf_hashes: usize,
+    /// `merge` invocations folding siblings (and implicit zeros) up to the root
+    pub merge_hashes: usize,
+}
+
+impl ProofCost {
+    /// total `Hasher` invocations: `leaf_hashes + merge_hashes`
+    pub fn hash_invocations(&self) -> usize {
+        self.leaf_hashes + self.merge_hashes
+    }
+}
+
+/// structural twin of `MerkleProof::compute_root`: walks the same height-by-height
+/// folding order, but counts `merge` calls instead of performing them, since the
+/// invocation count doesn't depend on the actual hash values.
+fn proof_cost<const DEPTH: usize>(
+    leaves_path: &[H256],
+    proof: &[H256],
+    mut keys: Vec<H256>,
+) -> Result<ProofCost> {
+    if keys.is_empty() {
+        return Err(Error::EmptyKeys);
+    } else if keys.len() != leaves_path.len() {
+        return Err(Error::IncorrectNumberOfLeaves {
+            expected: leaves_path.len(),
+            actual: keys.len(),
+        });
+    }
+    keys.sort_unstable();
+
+    let mut proof_index = 0;
+    // (path_index, key)
+    let mut current_nodes: Vec<(usize, H256)> = keys.into_iter().enumerate().collect();
+    let mut next_nodes: Vec<(usize, H256)> = Default::default();
+    let mut merge_hashes = 0;
+    for height in 0..=(DEPTH as u8) {
+        let mut key_idx = 0;
+        while key_idx < current_nodes.len() {
+            let (path_idx_a, key_a) = current_nodes[key_idx];
+            let parent_key_a = key_a.parent_path(height);
+
+            let mut non_sibling_nodes = Vec::with_capacity(2);
+            if key_idx + 1 < current_nodes.len() {
+                let (path_idx_b, key_b) = current_nodes[key_idx + 1];
+                let parent_key_b = key_b.parent_path(height);
+                if parent_key_a == parent_key_b {
+                    merge_hashes += 1;
+                    next_nodes.push((path_idx_a, key_a));
+                    key_idx += 2;
+                } else {
+                    non_sibling_nodes.push((path_idx_a, key_a));
+                    if key_idx + 2 == current_nodes.len() {
+                        non_sibling_nodes.push((path_idx_b, key_b));
+                    }
+                }
+            } else {
+                non_sibling_nodes.push((path_idx_a, key_a));
+            }
+
+            for (path_idx, current_key) in non_sibling_nodes.into_iter() {
+                let path = leaves_path[path_idx];
+                if path.get_bit(height) {
+                    if proof_index == proof.len() {
+                        return Err(Error::CorruptedProof);
+                    }
+                    proof_index += 1;
+                }
+                merge_hashes += 1;
+                next_nodes.push((path_idx, current_key));
+                key_idx += 1;
+            }
+        }
+        current_nodes = core::mem::take(&mut next_nodes);
+    }
+
+    if proof_index != proof.len() || current_nodes.len() != 1 {
+        return Err(Error::CorruptedProof);
+    }
+
+    Ok(ProofCost {
+        sibling_hashes: proof.len(),
+        leaf_hashes: leaves_path.len(),
+        merge_hashes,
+    })
+}
+
+/// validate and strip the `to_bytes` header, returning the leaf count and the
+/// remaining `leaves_path || proof` body
+fn decode_header(data: &[u8]) -> Result<(usize, &[u8])> {
+    if data.len() < 5 {
+        return Err(Error::CorruptedProof);
+    }
+    let version = data[0];
+    if version != PROOF_VERSION {
+        return Err(Error::InvalidCode(version));
+    }
+    let leaves_count = u32::from_le_bytes([data[1], data[2], data[3], data[4]]) as usize;
+    let body = &data[5..];
+    if body.len() % 32 != 0 || leaves_count > body.len() / 32 {
+        return Err(Error::CorruptedProof);
+    }
+    Ok((leaves_count, body))
 }
 
 /// An structure optimized for verify merkle proof
+///
+/// `DEPTH` plays the same role as on `MerkleProof`: it must match the tree/proof the
+/// bytes were produced from.
 #[derive(Debug, Clone)]
-pub struct CompiledMerkleProof(pub Vec<u8>);
+pub struct CompiledMerkleProof<const DEPTH: usize = { core::u8::MAX as usize }>(pub Vec<u8>);
+
+impl<const DEPTH: usize> CompiledMerkleProof<DEPTH> {
+    /// Parse a proof produced by `MerkleProof::to_bytes`, stripping the version
+    /// and leaf-count header and keeping the flat `leaves_path || proof` layout
+    /// that `compile()` produces.
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let (_leaves_count, body) = decode_header(data)?;
+        Ok(CompiledMerkleProof(body.to_vec()))
+    }
+
+    /// Serialize into the same canonical, self-describing layout as
+    /// `MerkleProof::to_bytes`: a 1-byte version, a 4-byte little-endian `leaves_count`,
+    /// then the flat `leaves_path || proof` bytes. `leaves_count` isn't recoverable from
+    /// the compiled bytes alone (unlike `MerkleProof`, whose `leaves_path` length IS the
+    /// leaf count), so the caller passes the count it intends to verify with.
+    pub fn to_bytes(self, leaves_count: usize) -> Vec<u8> {
+        let mut data = Vec::with_capacity(5 + self.0.len());
+        data.push(PROOF_VERSION);
+        data.extend_from_slice(&(leaves_count as u32).to_le_bytes());
+        data.extend_from_slice(&self.0);
+        data
+    }
 
-impl CompiledMerkleProof {
     pub fn compute_root<H: Hasher + Default>(&self, leaves: Vec<(H256, H256)>) -> Result<H256> {
         if self.0.len() % 32 != 0 {
             return Err(Error::CorruptedProof);
@@ -190,7 +420,7 @@ impl CompiledMerkleProof {
             data.copy_from_slice(&self.0[offset..offset + 32]);
             proof.push(H256::from(data));
         }
-        MerkleProof::new(leaves_path, proof).compute_root::<H>(leaves)
+        MerkleProof::<DEPTH>::new(leaves_path, proof).compute_root::<H>(leaves)
     }
 
     pub fn verify<H: Hasher + Default>(
@@ -201,10 +431,79 @@ impl CompiledMerkleProof {
         let calculated_root = self.compute_root::<H>(leaves)?;
         Ok(&calculated_root == root)
     }
+
+    /// Like `compute_root`, but leaves are `(key, Option<value>)` pairs; see
+    /// `MerkleProof::compute_root_opt`. Takes `&self`, so the same compiled proof can
+    /// be reused to compute the root before and after an update by calling this twice
+    /// with different `Option` values per key.
+    pub fn compute_root_opt<H: Hasher + Default>(
+        &self,
+        leaves: Vec<(H256, Option<H256>)>,
+    ) -> Result<H256> {
+        let leaves = leaves
+            .into_iter()
+            .map(|(key, value)| (key, value.unwrap_or_else(H256::zero)))
+            .collect();
+        self.compute_root::<H>(leaves)
+    }
+
+    /// Like `verify`, but accepts `(key, Option<value>)` pairs; see `compute_root_opt`.
+    pub fn verify_opt<H: Hasher + Default>(
+        &self,
+        root: &H256,
+        leaves: Vec<(H256, Option<H256>)>,
+    ) -> Result<bool> {
+        let calculated_root = self.compute_root_opt::<H>(leaves)?;
+        Ok(&calculated_root == root)
+    }
+
+    /// Like `MerkleProof::cost`, but for a compiled proof; see [`ProofCost`].
+    pub fn cost(&self, keys: Vec<H256>) -> Result<ProofCost> {
+        if self.0.len() % 32 != 0 {
+            return Err(Error::CorruptedProof);
+        }
+        if self.0.len() / 32 < keys.len() {
+            return Err(Error::CorruptedProof);
+        }
+
+        let sibling_node_size = self.0.len() / 32 - keys.len();
+        let mut data = [0u8; 32];
+        let mut leaves_path = Vec::with_capacity(keys.len());
+        let mut proof = Vec::with_capacity(sibling_node_size);
+        for idx in 0..keys.len() {
+            let offset = idx * 32;
+            data.copy_from_slice(&self.0[offset..offset + 32]);
+            leaves_path.push(H256::from(data));
+        }
+        for idx in 0..sibling_node_size {
+            let offset = (idx + keys.len()) * 32;
+            data.copy_from_slice(&self.0[offset..offset + 32]);
+            proof.push(H256::from(data));
+        }
+        proof_cost::<DEPTH>(&leaves_path, &proof, keys)
+    }
 }
 
-impl Into<Vec<u8>> for CompiledMerkleProof {
+impl<const DEPTH: usize> Into<Vec<u8>> for CompiledMerkleProof<DEPTH> {
     fn into(self) -> Vec<u8> {
         self.0
     }
 }
+
+/// Verify a proof transcript produced by `CompiledMerkleProof::to_bytes` (or
+/// `MerkleProof::to_bytes`, whose format is identical) against `root`, without ever
+/// constructing a `SparseMerkleTree` — the entry point for verifiers that only hold the
+/// transcript bytes, the claimed root, and the leaves being proven, e.g. on-chain or
+/// light-client contexts. Malformed `format_bytes` return an `Error` rather than
+/// panicking or silently computing a wrong root.
+///
+/// Assumes the default, full 256-bit `DEPTH`: a transcript from a shallower tree
+/// should go through `CompiledMerkleProof::<DEPTH>::from_bytes` and `verify` instead.
+pub fn verify_compiled<H: Hasher + Default>(
+    root: &H256,
+    format_bytes: &[u8],
+    leaves: Vec<(H256, H256)>,
+) -> Result<bool> {
+    CompiledMerkleProof::<{ core::u8::MAX as usize }>::from_bytes(format_bytes)?
+        .verify::<H>(root, leaves)
+}
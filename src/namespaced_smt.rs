@@ -0,0 +1,209 @@
+//! A manager for many independent sparse Merkle trees, discriminated by a namespace
+//! id `X`, that share a single backing [`Store`] the way `xsmt`'s `VsSmt2` does. This
+//! lets one process host per-account or per-shard trees cheaply instead of pairing a
+//! separate `SparseMerkleTree` with a separate `Store` for each of them.
+//!
+//! `X` only needs `AsRef<[u8]>` (plus the bounds `roots` needs to key a map by it), so
+//! a namespace id isn't limited to a fixed-size array like `[u8; 16]`; a `Vec<u8>` or
+//! `String` namespace works the same way (see `test_namespaced_smt_variable_length_id`
+//! in `tests.rs`).
+//!
+//! Branch and leaf store keys are derived by hashing `(x, node_key)` (see
+//! [`namespaced_branch_key`]/[`namespaced_leaf_key`]), so two namespaces with
+//! identical contents never collide in the shared store. Each namespace's root is
+//! tracked separately in memory; an empty namespace reports [`H256::zero()`] without
+//! ever touching the store.
+//!
+//! The namespace id never enters the hash computation itself, only the store keys, so
+//! two namespaces holding identical leaves compute identical roots while still
+//! remaining isolated on update (see `test_namespaced_smt_identical_leaves_same_subroot`
+//! in `tests.rs`).
+
+use crate::{
+    default_store::Map,
+    error::Result,
+    merkle_proof::MerkleProof,
+    traits::{Hasher, Leaf, Store, Value},
+    tree::{BranchKey, BranchNode, LeafNode, SparseMerkleTree},
+    vec::Vec,
+    H256,
+};
+use core::hash::Hash;
+use core::marker::PhantomData;
+
+/// the store key a namespaced branch at `(x, branch_key)` is kept under
+fn namespaced_branch_key<X: AsRef<[u8]>, H: Hasher + Default>(
+    x: &X,
+    branch_key: &BranchKey,
+) -> BranchKey {
+    let mut hasher = H::default();
+    hasher.write_byte(0);
+    for b in x.as_ref() {
+        hasher.write_byte(*b);
+    }
+    hasher.write_byte(branch_key.height());
+    hasher.write_h256(&branch_key.node_key());
+    BranchKey::new(branch_key.height(), hasher.finish())
+}
+
+/// the store key a namespaced leaf at `(x, leaf_key)` is kept under
+fn namespaced_leaf_key<X: AsRef<[u8]>, H: Hasher + Default>(x: &X, leaf_key: &H256) -> H256 {
+    let mut hasher = H::default();
+    hasher.write_byte(1);
+    for b in x.as_ref() {
+        hasher.write_byte(*b);
+    }
+    hasher.write_h256(leaf_key);
+    hasher.finish()
+}
+
+/// read-only namespaced view of the shared store, used for `get`/`merkle_proof`; the
+/// mutating methods are never called on those paths
+struct NamespacedStoreView<'a, X, H, S> {
+    x: &'a X,
+    store: &'a S,
+    phantom: PhantomData<H>,
+}
+
+impl<'a, X: AsRef<[u8]>, H: Hasher + Default, V, S: Store<V>> Store<V>
+    for NamespacedStoreView<'a, X, H, S>
+{
+    fn get_branch(&self, branch_key: &BranchKey) -> Result<Option<BranchNode>> {
+        self.store
+            .get_branch(&namespaced_branch_key::<X, H>(self.x, branch_key))
+    }
+    fn get_leaf(&self, leaf_key: &H256) -> Result<Option<LeafNode<V>>> {
+        self.store.get_leaf(&namespaced_leaf_key::<X, H>(self.x, leaf_key))
+    }
+    fn insert_branch(&mut self, _branch_key: BranchKey, _branch: BranchNode) -> Result<()> {
+        Ok(())
+    }
+    fn insert_leaf(&mut self, _leaf_key: H256, _leaf: LeafNode<V>) -> Result<()> {
+        Ok(())
+    }
+    fn remove_branch(&mut self, _branch_key: &BranchKey) -> Result<()> {
+        Ok(())
+    }
+    fn remove_leaf(&mut self, _leaf_key: &H256) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// namespaced view of the shared store used for `update`, backed by a mutable
+/// reference so writes land in the real store
+struct NamespacedStoreViewMut<'a, X, H, S> {
+    x: &'a X,
+    store: &'a mut S,
+    phantom: PhantomData<H>,
+}
+
+impl<'a, X: AsRef<[u8]>, H: Hasher + Default, V, S: Store<V>> Store<V>
+    for NamespacedStoreViewMut<'a, X, H, S>
+{
+    fn get_branch(&self, branch_key: &BranchKey) -> Result<Option<BranchNode>> {
+        self.store
+            .get_branch(&namespaced_branch_key::<X, H>(self.x, branch_key))
+    }
+    fn get_leaf(&self, leaf_key: &H256) -> Result<Option<LeafNode<V>>> {
+        self.store.get_leaf(&namespaced_leaf_key::<X, H>(self.x, leaf_key))
+    }
+    fn insert_branch(&mut self, branch_key: BranchKey, branch: BranchNode) -> Result<()> {
+        self.store
+            .insert_branch(namespaced_branch_key::<X, H>(self.x, &branch_key), branch)
+    }
+    fn insert_leaf(&mut self, leaf_key: H256, leaf: LeafNode<V>) -> Result<()> {
+        self.store
+            .insert_leaf(namespaced_leaf_key::<X, H>(self.x, &leaf_key), leaf)
+    }
+    fn remove_branch(&mut self, branch_key: &BranchKey) -> Result<()> {
+        self.store
+            .remove_branch(&namespaced_branch_key::<X, H>(self.x, branch_key))
+    }
+    fn remove_leaf(&mut self, leaf_key: &H256) -> Result<()> {
+        self.store.remove_leaf(&namespaced_leaf_key::<X, H>(self.x, leaf_key))
+    }
+}
+
+/// Manages many sparse Merkle trees, each identified by a namespace id `x`, over one
+/// shared backing store. Every root a namespace has ever had is kept in
+/// [`history`](NamespacedSmt::history), oldest first, so callers can audit or pin an
+/// earlier version without needing the store to retain the nodes behind it.
+pub struct NamespacedSmt<X, H, V, S> {
+    store: S,
+    roots: Map<X, H256>,
+    roots_history: Map<X, Vec<H256>>,
+    phantom: PhantomData<(H, V)>,
+}
+
+impl<X, H, V, S: Default> Default for NamespacedSmt<X, H, V, S> {
+    fn default() -> Self {
+        NamespacedSmt {
+            store: S::default(),
+            roots: Map::default(),
+            roots_history: Map::default(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<X: AsRef<[u8]> + Clone + Eq + Hash + Ord, H: Hasher + Default, V: Value + Leaf, S: Store<V>>
+    NamespacedSmt<X, H, V, S>
+{
+    /// Get backend store shared by every namespace
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Merkle root of namespace `x`; `H256::zero()` if `x` has no entries
+    pub fn root(&self, x: &X) -> H256 {
+        self.roots.get(x).copied().unwrap_or_else(H256::zero)
+    }
+
+    /// Check whether namespace `x` is empty
+    pub fn is_empty(&self, x: &X) -> bool {
+        self.root(x).is_zero()
+    }
+
+    /// Every root namespace `x` has had, oldest first; empty if `x` has never been
+    /// updated. This is a log of root values only — the store itself may have
+    /// overwritten the branch/leaf nodes an older root touched, so an entry here isn't
+    /// on its own a guarantee that tree can still be walked.
+    pub fn history(&self, x: &X) -> &[H256] {
+        self.roots_history.get(x).map_or(&[], Vec::as_slice)
+    }
+
+    /// Get value of a leaf in namespace `x`
+    pub fn get(&self, x: &X, key: &H256) -> Result<V> {
+        let view = NamespacedStoreView {
+            x,
+            store: &self.store,
+            phantom: PhantomData::<H>,
+        };
+        SparseMerkleTree::<H, V, _>::new(self.root(x), view).get(key)
+    }
+
+    /// Update a leaf in namespace `x`, return the new root of that namespace
+    pub fn update(&mut self, x: &X, key: H256, value: V) -> Result<H256> {
+        let root = self.root(x);
+        let view = NamespacedStoreViewMut {
+            x,
+            store: &mut self.store,
+            phantom: PhantomData::<H>,
+        };
+        let mut tree = SparseMerkleTree::<H, V, _>::new(root, view);
+        let new_root = *tree.update(key, value)?;
+        self.roots.insert(x.clone(), new_root);
+        self.roots_history.entry(x.clone()).or_default().push(new_root);
+        Ok(new_root)
+    }
+
+    /// Generate a merkle proof over `keys` in namespace `x`
+    pub fn merkle_proof(&self, x: &X, keys: Vec<H256>) -> Result<MerkleProof> {
+        let view = NamespacedStoreView {
+            x,
+            store: &self.store,
+            phantom: PhantomData::<H>,
+        };
+        SparseMerkleTree::<H, V, _>::new(self.root(x), view).merkle_proof(keys)
+    }
+}
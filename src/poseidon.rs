@@ -0,0 +1,151 @@
+//! A Poseidon hasher over the BN254 scalar field, gated behind the `poseidon`
+//! feature, so roots and proofs produced by `SparseMerkleTree` can be checked inside
+//! an arithmetic circuit (Groth16/PLONK) — infeasible with a byte-oriented hash like
+//! `Blake2bHasher`/[`Blake3Hasher`](crate::blake3::Blake3Hasher).
+//!
+//! This is a width-3 (`t = 3`) Poseidon sponge with a rate of 2 field elements and a
+//! capacity of 1: 8 full rounds (S-box `x^5` applied to every lane) and 57 partial
+//! rounds (S-box only on the first lane), each round adding round constants to every
+//! lane and then mixing lanes through an MDS matrix, matching the parameters
+//! recommended for a 2-to-1 merge over a ~254-bit field. The permutation runs every
+//! time the rate fills (every two absorbed elements), and once more at `finish` if a
+//! partial block is still buffered, so a `merge` call's four inputs (height, node
+//! key, left, right) and a `hash_leaf` call's two (key, value) are both absorbed a
+//! full sponge block at a time.
+//!
+//! Round constants and the MDS matrix are generated once per permutation by
+//! expanding a fixed domain-separation label through repeated Blake2b hashing and
+//! reducing each output mod the field order (see `round_constants`/`mds_matrix`).
+//! They aren't pinned to any external reference vectors, so don't expect
+//! interoperability with other Poseidon implementations — only with itself.
+//!
+//! Every `write_h256` input is reduced mod the field order via
+//! `Fr::from_le_bytes_mod_order`, so values at or above the modulus never panic —
+//! they just lose their top bits, same as any other field absorption. A
+//! `write_byte` input is absorbed as the small field element `Fr::from(b as u64)`.
+
+use crate::{traits::Hasher, H256};
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, Field, PrimeField, Zero};
+
+/// sponge width
+const T: usize = 3;
+/// sponge rate (elements absorbed/squeezed per permutation); `T - 1`, leaving one
+/// capacity lane
+const RATE: usize = T - 1;
+const FULL_ROUNDS: usize = 8;
+const PARTIAL_ROUNDS: usize = 57;
+
+/// Expand `(domain, index)` into a field element via Blake2b, the same
+/// domain-separated expansion approach real Poseidon parameter generators use (there
+/// with a Grain LFSR in place of Blake2b).
+fn expand_field_element(domain: &[u8], index: u64) -> Fr {
+    let mut hasher = blake2b_rs::Blake2bBuilder::new(32)
+        .personal(b"SMTPoseidn")
+        .build();
+    hasher.update(domain);
+    hasher.update(&index.to_le_bytes());
+    let mut buf = [0u8; 32];
+    hasher.finalize(&mut buf);
+    Fr::from_le_bytes_mod_order(&buf)
+}
+
+/// one set of `T` round constants per round, `FULL_ROUNDS + PARTIAL_ROUNDS` rounds
+fn round_constants() -> Vec<[Fr; T]> {
+    (0..(FULL_ROUNDS + PARTIAL_ROUNDS))
+        .map(|round| {
+            let mut lanes = [Fr::zero(); T];
+            for (i, lane) in lanes.iter_mut().enumerate() {
+                *lane = expand_field_element(b"rc", (round * T + i) as u64);
+            }
+            lanes
+        })
+        .collect()
+}
+
+/// a Cauchy matrix (`m[i][j] = 1 / (x_i + y_j)` for distinct `x_i`, `y_j`), the
+/// standard construction guaranteeing an MDS matrix
+fn mds_matrix() -> [[Fr; T]; T] {
+    let xs: Vec<Fr> = (0..T).map(|i| expand_field_element(b"mds-x", i as u64)).collect();
+    let ys: Vec<Fr> = (0..T).map(|j| expand_field_element(b"mds-y", j as u64)).collect();
+    let mut m = [[Fr::zero(); T]; T];
+    for i in 0..T {
+        for j in 0..T {
+            // `xs[i] + ys[j]` is never zero in practice over a ~254-bit field
+            m[i][j] = (xs[i] + ys[j]).inverse().expect("non-invertible MDS entry");
+        }
+    }
+    m
+}
+
+fn permute(state: &mut [Fr; T]) {
+    let rc = round_constants();
+    let mds = mds_matrix();
+    for (round, constants) in rc.iter().enumerate() {
+        for (lane, constant) in state.iter_mut().zip(constants.iter()) {
+            *lane += constant;
+        }
+        if round < FULL_ROUNDS / 2 || round >= FULL_ROUNDS / 2 + PARTIAL_ROUNDS {
+            for lane in state.iter_mut() {
+                *lane = lane.pow([5u64]);
+            }
+        } else {
+            state[0] = state[0].pow([5u64]);
+        }
+        let mut next = [Fr::zero(); T];
+        for (i, row) in mds.iter().enumerate() {
+            for (j, weight) in row.iter().enumerate() {
+                next[i] += *weight * state[j];
+            }
+        }
+        *state = next;
+    }
+}
+
+fn fr_to_h256(fr: Fr) -> H256 {
+    let bytes = fr.into_bigint().to_bytes_le();
+    let mut buf = [0u8; 32];
+    let len = bytes.len().min(32);
+    buf[..len].copy_from_slice(&bytes[..len]);
+    buf.into()
+}
+
+pub struct PoseidonHasher {
+    state: [Fr; T],
+    rate_pos: usize,
+}
+
+impl Default for PoseidonHasher {
+    fn default() -> Self {
+        PoseidonHasher {
+            state: [Fr::zero(); T],
+            rate_pos: 0,
+        }
+    }
+}
+
+impl PoseidonHasher {
+    fn absorb(&mut self, element: Fr) {
+        self.state[self.rate_pos] += element;
+        self.rate_pos += 1;
+        if self.rate_pos == RATE {
+            permute(&mut self.state);
+            self.rate_pos = 0;
+        }
+    }
+}
+
+impl Hasher for PoseidonHasher {
+    fn write_h256(&mut self, h: &H256) {
+        self.absorb(Fr::from_le_bytes_mod_order(h.as_slice()));
+    }
+    fn write_byte(&mut self, b: u8) {
+        self.absorb(Fr::from(b as u64));
+    }
+    fn finish(mut self) -> H256 {
+        if self.rate_pos != 0 {
+            permute(&mut self.state);
+        }
+        fr_to_h256(self.state[0])
+    }
+}
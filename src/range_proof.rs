@@ -0,0 +1,450 @@
+//! Range proofs over contiguous key intervals.
+//!
+//! `H256`'s `Ord` compares bits from the highest to the lowest, which is exactly the
+//! order the tree is walked in. That means the non-zero leaves whose keys fall inside
+//! a `KeyRange` always occupy a contiguous run of paths, so a single proof can attest
+//! that a sorted leaf list is the *complete* set of entries in that interval rather
+//! than merely a valid subset of them.
+
+use crate::{
+    error::{Error, Result},
+    merkle_proof::MerkleProof,
+    traits::{Hasher, Leaf, Store, Value},
+    tree::{BranchKey, SparseMerkleTree},
+    vec::Vec,
+    H256,
+};
+use core::ops::{Range, RangeFrom, RangeFull, RangeTo};
+
+/// A half-open key interval `[start, end)`.
+///
+/// `None` on either side means unbounded, mirroring the `[..]`, `[s..]`, `[..e]` and
+/// `[s..e]` forms of a normal Rust range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct KeyRange {
+    pub start: Option<H256>,
+    pub end: Option<H256>,
+}
+
+impl KeyRange {
+    /// The full key space `[..]`.
+    pub fn full() -> Self {
+        KeyRange {
+            start: None,
+            end: None,
+        }
+    }
+
+    /// `[start..]`
+    pub fn from(start: H256) -> Self {
+        KeyRange {
+            start: Some(start),
+            end: None,
+        }
+    }
+
+    /// `[..end]`
+    pub fn to(end: H256) -> Self {
+        KeyRange {
+            start: None,
+            end: Some(end),
+        }
+    }
+
+    /// `[start..end]`
+    pub fn new(start: H256, end: H256) -> Self {
+        KeyRange {
+            start: Some(start),
+            end: Some(end),
+        }
+    }
+
+    fn contains(&self, key: &H256) -> bool {
+        self.start.map_or(true, |start| key >= &start) && self.end.map_or(true, |end| key < &end)
+    }
+
+    /// whether the closed interval `[min, max]` could contain a key in this range
+    fn overlaps(&self, min: &H256, max: &H256) -> bool {
+        if let Some(start) = self.start {
+            if max < &start {
+                return false;
+            }
+        }
+        if let Some(end) = self.end {
+            if min >= &end {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl From<RangeFull> for KeyRange {
+    fn from(_range: RangeFull) -> Self {
+        KeyRange::full()
+    }
+}
+
+impl From<RangeFrom<H256>> for KeyRange {
+    fn from(range: RangeFrom<H256>) -> Self {
+        KeyRange::from(range.start)
+    }
+}
+
+impl From<RangeTo<H256>> for KeyRange {
+    fn from(range: RangeTo<H256>) -> Self {
+        KeyRange::to(range.end)
+    }
+}
+
+impl From<Range<H256>> for KeyRange {
+    fn from(range: Range<H256>) -> Self {
+        KeyRange::new(range.start, range.end)
+    }
+}
+
+/// A proof that `leaves` is exactly the complete, sorted set of non-zero leaves whose
+/// keys fall inside a `KeyRange`.
+///
+/// In addition to the usual sibling path for the included leaves, the proof carries
+/// two edge anchors: the predecessor leaf just below the range and the successor leaf
+/// just at or above it, when one exists. When a side has no such leaf (an unbounded
+/// `[..]`/`[..end]`/`[start..]` side, or simply nothing beyond the range on that side),
+/// the anchor falls back to the range bound itself (or the absolute min/max key for an
+/// unbounded side), proven with whatever value the tree holds there (zero when the
+/// key is absent). Either way, every anchor is a real `(key, value)`
+/// pair actually in the proof's key set, so `verify`'s pairwise completeness check
+/// always has something to pair the leftmost/rightmost in-range leaf against — there
+/// is no edge left unguarded the way an `Option`-shaped "boundary if one exists" would
+/// leave one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeProof {
+    /// anchor (key, value) for the left edge of the range
+    left_anchor: (H256, H256),
+    /// anchor (key, value) for the right edge of the range
+    right_anchor: (H256, H256),
+    /// sibling path proof covering the in-range leaves and the two anchors
+    proof: MerkleProof,
+}
+
+impl RangeProof {
+    /// Verify that `leaves` (sorted, in-range) is the complete set of non-zero
+    /// entries inside `range`, and that the reconstructed root matches `root`.
+    pub fn verify<H: Hasher + Default>(
+        &self,
+        root: &H256,
+        range: KeyRange,
+        leaves: Vec<(H256, H256)>,
+    ) -> Result<bool> {
+        for (key, _value) in &leaves {
+            if !range.contains(key) {
+                return Err(Error::CorruptedProof);
+            }
+        }
+
+        let mut all_leaves: Vec<(H256, H256)> = core::iter::once(self.left_anchor)
+            .chain(leaves.into_iter())
+            .chain(core::iter::once(self.right_anchor))
+            .collect();
+        all_leaves.sort_unstable_by_key(|(k, _v)| *k);
+        all_leaves.dedup_by_key(|(k, _v)| *k);
+
+        let leaves_path = self.proof.leaves_path();
+        if leaves_path.len() != all_leaves.len() {
+            return Ok(false);
+        }
+        // A standard multiproof over `all_leaves` only proves each key's own
+        // membership; a hidden in-range leaf between two consecutive included keys
+        // would simply surface as an opaque non-zero sibling hash and still
+        // reconstruct the claimed root. Rule that out here: for every consecutive
+        // pair, below their fork height neither key's path may carry a non-zero
+        // sibling on the side facing the other key (right of the lower key, left of
+        // the higher one), since such a sibling would be the root of a (possibly
+        // hidden) subtree squeezed strictly between them.
+        for (idx_a, window) in all_leaves.windows(2).enumerate() {
+            let (key_a, _) = window[0];
+            let (key_b, _) = window[1];
+            let fork_height = key_a.fork_height(&key_b);
+            let idx_b = idx_a + 1;
+            if has_non_zero_sibling_between(&leaves_path[idx_a], &key_a, fork_height, true)
+                || has_non_zero_sibling_between(&leaves_path[idx_b], &key_b, fork_height, false)
+            {
+                return Ok(false);
+            }
+        }
+
+        let computed_root = self.proof.clone().compute_root::<H>(all_leaves)?;
+        Ok(&computed_root == root)
+    }
+}
+
+/// whether `path` (the `MerkleProof` sibling bitmap of `key`) marks a non-zero sibling
+/// at any height strictly below `height` that lies on `key`'s right
+/// (`toward_right == true`) or left (`toward_right == false`) side
+fn has_non_zero_sibling_between(path: &H256, key: &H256, height: u8, toward_right: bool) -> bool {
+    (0..height).any(|h| path.get_bit(h) && key.get_bit(h) != toward_right)
+}
+
+/// the greatest possible `H256` key, used as the right edge anchor's fallback key
+/// for an unbounded (`[start..]`) range
+fn h256_max() -> H256 {
+    [0xffu8; 32].into()
+}
+
+/// closed bounds `[min, max]` of keys a subtree can contain, given the bits fixed
+/// above `height` (`prefix`) and `height..=0` still free
+fn subtree_bounds(prefix: H256, height: u8) -> (H256, H256) {
+    let min_key = prefix;
+    let mut max_key = prefix;
+    for h in 0..=height {
+        max_key.set_bit(h);
+    }
+    (min_key, max_key)
+}
+
+impl<H: Hasher + Default, V: Value + Leaf, S: Store<V>> SparseMerkleTree<H, V, S> {
+    /// Generate a proof that the returned, sorted `(key, value)` list is exactly the
+    /// complete set of non-zero leaves whose keys fall in `range`.
+    pub fn merkle_range_proof(
+        &self,
+        range: impl Into<KeyRange>,
+    ) -> Result<(Vec<(H256, H256)>, RangeProof)> {
+        let range = range.into();
+
+        let mut leaves = self.leaves_in_range(&range)?;
+        leaves.sort_unstable_by_key(|(k, _v)| *k);
+
+        let left_anchor = self.edge_anchor(self.predecessor(range.start)?, range.start, false)?;
+        let right_anchor = self.edge_anchor(self.successor(range.end)?, range.end, true)?;
+
+        let mut keys: Vec<H256> = core::iter::once(left_anchor.0)
+            .chain(leaves.iter().map(|(k, _)| *k))
+            .chain(core::iter::once(right_anchor.0))
+            .collect();
+        keys.sort_unstable();
+        keys.dedup();
+
+        let proof = self.merkle_proof(keys)?;
+        Ok((
+            leaves,
+            RangeProof {
+                left_anchor,
+                right_anchor,
+                proof,
+            },
+        ))
+    }
+
+    /// Like `merkle_range_proof`, but proves exactly `included_keys` instead of
+    /// scanning for the complete in-range set, so a test can build a maliciously
+    /// minimal `RangeProof` that never mentions an interior leaf in the first place.
+    #[cfg(test)]
+    pub(crate) fn merkle_range_proof_over(
+        &self,
+        range: impl Into<KeyRange>,
+        mut included_keys: Vec<H256>,
+    ) -> Result<(Vec<(H256, H256)>, RangeProof)> {
+        let range = range.into();
+        included_keys.sort_unstable();
+        included_keys.dedup();
+
+        let mut leaves = Vec::with_capacity(included_keys.len());
+        for key in &included_keys {
+            let value = self.get(key)?.leaf_hash::<H>();
+            if !value.is_zero() {
+                leaves.push((*key, value));
+            }
+        }
+
+        let left_anchor = self.edge_anchor(self.predecessor(range.start)?, range.start, false)?;
+        let right_anchor = self.edge_anchor(self.successor(range.end)?, range.end, true)?;
+
+        let mut keys: Vec<H256> = core::iter::once(left_anchor.0)
+            .chain(included_keys.into_iter())
+            .chain(core::iter::once(right_anchor.0))
+            .collect();
+        keys.sort_unstable();
+        keys.dedup();
+
+        let proof = self.merkle_proof(keys)?;
+        Ok((
+            leaves,
+            RangeProof {
+                left_anchor,
+                right_anchor,
+                proof,
+            },
+        ))
+    }
+
+    /// `boundary` from `predecessor`/`successor` if one was found; otherwise anchor
+    /// on the range's own bound (`range_bound`), or the absolute min/max key if that
+    /// side is unbounded, proven with whatever value the tree holds at that key (zero
+    /// when absent). Either way the result is a real `(key,
+    /// value)` pair that becomes one endpoint of `RangeProof::verify`'s pairwise
+    /// completeness check, so every edge — bounded or not — ends up guarded.
+    fn edge_anchor(
+        &self,
+        boundary: Option<(H256, H256)>,
+        range_bound: Option<H256>,
+        is_right_edge: bool,
+    ) -> Result<(H256, H256)> {
+        match boundary {
+            Some(anchor) => Ok(anchor),
+            None => {
+                let key = range_bound.unwrap_or_else(|| {
+                    if is_right_edge {
+                        h256_max()
+                    } else {
+                        H256::zero()
+                    }
+                });
+                let value = self.get(&key)?.leaf_hash::<H>();
+                Ok((key, value))
+            }
+        }
+    }
+
+    /// Descend the tree once, collecting every non-zero leaf whose key is in `range`.
+    /// Subtrees whose full key span can't overlap `range` are pruned without being
+    /// visited, so cost is proportional to the range's coverage, not the tree's depth.
+    fn leaves_in_range(&self, range: &KeyRange) -> Result<Vec<(H256, H256)>> {
+        let mut results = Vec::new();
+        if self.is_empty() {
+            return Ok(results);
+        }
+        let mut stack = vec![(core::u8::MAX, H256::zero())];
+        while let Some((height, prefix)) = stack.pop() {
+            let branch = match self.store().get_branch(&BranchKey::new(height, prefix))? {
+                Some(branch) => branch,
+                None => continue,
+            };
+            for (bit, child) in [(false, branch.left()), (true, branch.right())] {
+                if child.is_zero() {
+                    continue;
+                }
+                let mut candidate = prefix;
+                if bit {
+                    candidate.set_bit(height);
+                }
+                let (min_key, max_key) = subtree_bounds(candidate, height);
+                if !range.overlaps(&min_key, &max_key) {
+                    continue;
+                }
+                if height == 0 {
+                    if range.contains(&candidate) {
+                        if let Some(leaf) = self.store().get_leaf(&candidate)? {
+                            let value = leaf.into_value()?.leaf_hash::<H>();
+                            if !value.is_zero() {
+                                results.push((candidate, value));
+                            }
+                        }
+                    }
+                } else {
+                    stack.push((height - 1, candidate));
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    /// greatest non-zero leaf key strictly below `bound`
+    fn predecessor(&self, bound: Option<H256>) -> Result<Option<(H256, H256)>> {
+        let bound = match bound {
+            Some(bound) => bound,
+            None => return Ok(None),
+        };
+        if self.is_empty() {
+            return Ok(None);
+        }
+        let mut best: Option<(H256, H256)> = None;
+        let mut stack = vec![(core::u8::MAX, H256::zero())];
+        while let Some((height, prefix)) = stack.pop() {
+            let branch = match self.store().get_branch(&BranchKey::new(height, prefix))? {
+                Some(branch) => branch,
+                None => continue,
+            };
+            for (bit, child) in [(false, branch.left()), (true, branch.right())] {
+                if child.is_zero() {
+                    continue;
+                }
+                let mut candidate = prefix;
+                if bit {
+                    candidate.set_bit(height);
+                }
+                let (min_key, max_key) = subtree_bounds(candidate, height);
+                if min_key >= bound {
+                    continue;
+                }
+                if let Some((best_key, _)) = best {
+                    if max_key <= best_key {
+                        continue;
+                    }
+                }
+                if height == 0 {
+                    if candidate < bound {
+                        if let Some(leaf) = self.store().get_leaf(&candidate)? {
+                            let value = leaf.into_value()?.leaf_hash::<H>();
+                            if !value.is_zero() {
+                                best = Some((candidate, value));
+                            }
+                        }
+                    }
+                } else {
+                    stack.push((height - 1, candidate));
+                }
+            }
+        }
+        Ok(best)
+    }
+
+    /// least non-zero leaf key at or above `bound`
+    fn successor(&self, bound: Option<H256>) -> Result<Option<(H256, H256)>> {
+        let bound = match bound {
+            Some(bound) => bound,
+            None => return Ok(None),
+        };
+        if self.is_empty() {
+            return Ok(None);
+        }
+        let mut best: Option<(H256, H256)> = None;
+        let mut stack = vec![(core::u8::MAX, H256::zero())];
+        while let Some((height, prefix)) = stack.pop() {
+            let branch = match self.store().get_branch(&BranchKey::new(height, prefix))? {
+                Some(branch) => branch,
+                None => continue,
+            };
+            for (bit, child) in [(false, branch.left()), (true, branch.right())] {
+                if child.is_zero() {
+                    continue;
+                }
+                let mut candidate = prefix;
+                if bit {
+                    candidate.set_bit(height);
+                }
+                let (min_key, max_key) = subtree_bounds(candidate, height);
+                if max_key < bound {
+                    continue;
+                }
+                if let Some((best_key, _)) = best {
+                    if min_key >= best_key {
+                        continue;
+                    }
+                }
+                if height == 0 {
+                    if candidate >= bound {
+                        if let Some(leaf) = self.store().get_leaf(&candidate)? {
+                            let value = leaf.into_value()?.leaf_hash::<H>();
+                            if !value.is_zero() {
+                                best = Some((candidate, value));
+                            }
+                        }
+                    }
+                } else {
+                    stack.push((height - 1, candidate));
+                }
+            }
+        }
+        Ok(best)
+    }
+}
@@ -0,0 +1,230 @@
+//! A disk-backed [`Store`] on top of RocksDB, in the spirit of merkletree-rs's
+//! `db.rs`. Branch nodes and leaf values are serialized under their `H256` node key
+//! as the RocksDB key, so `SparseMerkleTree` can be pointed at a [`RocksDbStore`]
+//! exactly as it would at a [`DefaultStore`](crate::default_store::DefaultStore).
+//!
+//! Writes issued through [`Store::insert_branch`]/[`Store::insert_leaf`]/
+//! [`Store::remove_branch`]/[`Store::remove_leaf`] are buffered into an internal
+//! `WriteBatch` rather than put one at a time; [`RocksDbStore::commit_root`] flushes
+//! that batch together with the tree's new root as a single atomic write, so
+//! [`update_all`] can apply many keys and persist them (and the root) in one shot.
+//! On open, the current root is recovered from a dedicated root key, defaulting to
+//! `H256::zero()` when the database is fresh.
+//!
+//! Gated behind the `rocksdb` feature so the core crate stays `no_std` and
+//! dependency-free when this backend isn't enabled.
+
+use crate::{
+    error::{Error, Result},
+    string::String,
+    traits::{Hasher, Leaf, Store, Value},
+    tree::{BranchKey, BranchNode, LeafNode, SparseMerkleTree},
+    vec::Vec,
+    H256,
+};
+use core::convert::TryFrom;
+use core::marker::PhantomData;
+use rocksdb::{IteratorMode, WriteBatch, DB};
+use std::path::Path;
+
+const ROOT_KEY: &[u8] = b"root";
+const BRANCH_PREFIX: u8 = 0;
+const LEAF_PREFIX: u8 = 1;
+
+fn to_store_err<E: core::fmt::Display>(err: E) -> Error {
+    Error::Store(String::from(format!("{}", err)))
+}
+
+fn branch_store_key(branch_key: &BranchKey) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(34);
+    buf.push(BRANCH_PREFIX);
+    buf.push(branch_key.height());
+    buf.extend_from_slice(branch_key.node_key().as_slice());
+    buf
+}
+
+fn leaf_store_key(leaf_key: &H256) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(33);
+    buf.push(LEAF_PREFIX);
+    buf.extend_from_slice(leaf_key.as_slice());
+    buf
+}
+
+fn encode_branch(branch: &BranchNode) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(64);
+    buf.extend_from_slice(branch.left().as_slice());
+    buf.extend_from_slice(branch.right().as_slice());
+    buf
+}
+
+fn decode_branch(bytes: &[u8]) -> Result<BranchNode> {
+    if bytes.len() != 64 {
+        return Err(Error::Store(String::from("corrupted branch record")));
+    }
+    let mut left = [0u8; 32];
+    let mut right = [0u8; 32];
+    left.copy_from_slice(&bytes[..32]);
+    right.copy_from_slice(&bytes[32..]);
+    Ok(BranchNode::new(left.into(), right.into()))
+}
+
+fn encode_leaf<V: AsRef<[u8]>>(leaf: &LeafNode<V>) -> Vec<u8> {
+    match leaf {
+        LeafNode::Live { value, .. } => {
+            let mut buf = Vec::with_capacity(1 + value.as_ref().len());
+            buf.push(0);
+            buf.extend_from_slice(value.as_ref());
+            buf
+        }
+        LeafNode::Sealed { hash, .. } => {
+            let mut buf = Vec::with_capacity(33);
+            buf.push(1);
+            buf.extend_from_slice(hash.as_slice());
+            buf
+        }
+    }
+}
+
+fn decode_leaf<V: TryFrom<Vec<u8>>>(key: H256, bytes: &[u8]) -> Result<LeafNode<V>> {
+    match bytes.split_first() {
+        Some((0, value)) => Ok(LeafNode::Live {
+            key,
+            value: V::try_from(value.to_vec())
+                .map_err(|_| Error::Store(String::from("corrupted leaf record")))?,
+        }),
+        Some((1, hash)) if hash.len() == 32 => {
+            let mut buf = [0u8; 32];
+            buf.copy_from_slice(hash);
+            Ok(LeafNode::Sealed {
+                key,
+                hash: buf.into(),
+            })
+        }
+        _ => Err(Error::Store(String::from("corrupted leaf record"))),
+    }
+}
+
+/// A RocksDB-backed [`Store`]. Writes are buffered in a `WriteBatch` until
+/// [`commit_root`](RocksDbStore::commit_root) flushes them atomically with the new
+/// root.
+pub struct RocksDbStore<V> {
+    db: DB,
+    batch: WriteBatch,
+    phantom: PhantomData<V>,
+}
+
+impl<V> RocksDbStore<V> {
+    /// Open (or create) the RocksDB database at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let db = DB::open_default(path).map_err(to_store_err)?;
+        Ok(RocksDbStore {
+            db,
+            batch: WriteBatch::default(),
+            phantom: PhantomData,
+        })
+    }
+
+    /// The root as of the last `commit_root`, or `H256::zero()` for a fresh database.
+    pub fn root(&self) -> Result<H256> {
+        match self.db.get(ROOT_KEY).map_err(to_store_err)? {
+            None => Ok(H256::zero()),
+            Some(bytes) if bytes.len() == 32 => {
+                let mut buf = [0u8; 32];
+                buf.copy_from_slice(&bytes);
+                Ok(buf.into())
+            }
+            Some(_) => Err(Error::Store(String::from("corrupted root record"))),
+        }
+    }
+
+    /// Flush every branch/leaf write buffered since the last `commit_root`, together
+    /// with the new `root`, as a single atomic RocksDB write batch.
+    pub fn commit_root(&mut self, root: H256) -> Result<()> {
+        self.batch.put(ROOT_KEY, root.as_slice());
+        let batch = core::mem::take(&mut self.batch);
+        self.db.write(batch).map_err(to_store_err)
+    }
+
+    /// Delete every branch, leaf, and the root key, resetting the database to the
+    /// same empty state `open` would find a fresh path in. Unlike `commit_root`,
+    /// this reads the full keyspace to find what to delete, so it isn't meant for a
+    /// hot path; it mirrors `DefaultStore::clear` for tests and maintenance tooling.
+    pub fn clear(&mut self) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        for item in self.db.iterator(IteratorMode::Start) {
+            let (key, _value) = item.map_err(to_store_err)?;
+            batch.delete(key);
+        }
+        batch.delete(ROOT_KEY);
+        self.db.write(batch).map_err(to_store_err)
+    }
+}
+
+impl<V: AsRef<[u8]> + TryFrom<Vec<u8>>> Store<V> for RocksDbStore<V> {
+    fn get_branch(&self, branch_key: &BranchKey) -> Result<Option<BranchNode>> {
+        match self.db.get(branch_store_key(branch_key)).map_err(to_store_err)? {
+            Some(bytes) => Ok(Some(decode_branch(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+    fn get_leaf(&self, leaf_key: &H256) -> Result<Option<LeafNode<V>>> {
+        match self.db.get(leaf_store_key(leaf_key)).map_err(to_store_err)? {
+            Some(bytes) => Ok(Some(decode_leaf(*leaf_key, &bytes)?)),
+            None => Ok(None),
+        }
+    }
+    fn insert_branch(&mut self, branch_key: BranchKey, branch: BranchNode) -> Result<()> {
+        self.batch
+            .put(branch_store_key(&branch_key), encode_branch(&branch));
+        Ok(())
+    }
+    fn insert_leaf(&mut self, leaf_key: H256, leaf: LeafNode<V>) -> Result<()> {
+        self.batch.put(leaf_store_key(&leaf_key), encode_leaf(&leaf));
+        Ok(())
+    }
+    fn remove_branch(&mut self, branch_key: &BranchKey) -> Result<()> {
+        self.batch.delete(branch_store_key(branch_key));
+        Ok(())
+    }
+    fn remove_leaf(&mut self, leaf_key: &H256) -> Result<()> {
+        self.batch.delete(leaf_store_key(leaf_key));
+        Ok(())
+    }
+}
+
+/// Open the database at `path` and rebuild the tree sitting on top of it, recovering
+/// its root from the dedicated root key (`H256::zero()` for a fresh database).
+pub fn open_tree<H, V, P>(path: P) -> Result<SparseMerkleTree<H, V, RocksDbStore<V>>>
+where
+    H: Hasher + Default,
+    V: Value + Leaf + AsRef<[u8]> + TryFrom<Vec<u8>>,
+    P: AsRef<Path>,
+{
+    let store = RocksDbStore::open(path)?;
+    let root = store.root()?;
+    Ok(SparseMerkleTree::new(root, store))
+}
+
+/// Apply every `(key, value)` pair in `updates` to `tree`, then flush all the
+/// branches and leaves they touched together with the new root as one atomic write
+/// batch, instead of one RocksDB put per touched node.
+///
+/// Goes through `SparseMerkleTree::update_all` rather than one `update` per pair:
+/// `RocksDbStore`'s `get_branch`/`get_leaf` only ever read the database, not the
+/// still-unflushed writes sitting in `batch`, so a loop of individual `update` calls
+/// would have each pair's `recompute_root` read stale (pre-batch) branch state for
+/// every earlier pair in the same call and silently drop them from the root.
+/// `update_all` only tracks dirty nodes in memory, so it never hits that gap.
+pub fn update_all<H, V>(
+    tree: &mut SparseMerkleTree<H, V, RocksDbStore<V>>,
+    updates: Vec<(H256, V)>,
+) -> Result<H256>
+where
+    H: Hasher + Default,
+    V: Value + Leaf + AsRef<[u8]> + TryFrom<Vec<u8>>,
+{
+    tree.update_all(updates)?;
+    let root = *tree.root();
+    tree.store_mut().commit_root(root)?;
+    Ok(root)
+}
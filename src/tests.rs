@@ -1,7 +1,7 @@
 use super::*;
 use crate::{
-    blake2b::Blake2bHasher, default_store::DefaultStore, error::Error, MerkleProof,
-    SparseMerkleTree,
+    blake2b::Blake2bHasher, default_store::DefaultStore, error::Error, traits::Leaf,
+    CompiledMerkleProof, KeyRange, MerkleProof, SparseMerkleTree,
 };
 use proptest::prelude::*;
 
@@ -45,7 +45,7 @@ fn test_default_tree() {
 
 #[test]
 fn test_default_merkle_proof() {
-    let proof = MerkleProof::new(Default::default(), Default::default());
+    let proof: MerkleProof = MerkleProof::new(Default::default(), Default::default());
     let result = proof.compute_root::<Blake2bHasher>(vec![([42u8; 32].into(), [42u8; 32].into())]);
     assert_eq!(
         result.unwrap_err(),
@@ -55,7 +55,7 @@ fn test_default_merkle_proof() {
         }
     );
     // makes room for leaves
-    let proof = MerkleProof::new(vec![Vec::new()], Default::default());
+    let proof: MerkleProof = MerkleProof::new(vec![Vec::new()], Default::default());
     let root = proof
         .compute_root::<Blake2bHasher>(vec![([42u8; 32].into(), [42u8; 32].into())])
         .expect("compute root");
@@ -99,6 +99,842 @@ fn test_merkle_root() {
     assert_eq!(tree.root(), &expected_root);
 }
 
+#[test]
+fn test_merkle_range_proof() {
+    let mut tree = SMT::default();
+    let mut keys: Vec<H256> = Vec::new();
+    for i in 0u8..10 {
+        let mut key = H256::zero();
+        key.set_bit(i);
+        // `i + 1`: a zero value would delete the key instead of storing it
+        tree.update(key, [i + 1; 32].into()).expect("update");
+        keys.push(key);
+    }
+    keys.sort_unstable();
+
+    // range covering the middle keys only
+    let range = KeyRange::new(keys[2], keys[7]);
+    let (leaves, proof) = tree.merkle_range_proof(range).expect("range proof");
+    assert_eq!(leaves, keys[2..7].iter().map(|k| (*k, tree.get(k).unwrap())).collect::<Vec<_>>());
+    assert!(proof
+        .verify::<Blake2bHasher>(tree.root(), range, leaves)
+        .expect("verify range proof"));
+
+    // full range must return every leaf
+    let (all_leaves, full_proof) = tree
+        .merkle_range_proof(KeyRange::full())
+        .expect("range proof");
+    assert_eq!(all_leaves.len(), keys.len());
+    assert!(full_proof
+        .verify::<Blake2bHasher>(tree.root(), KeyRange::full(), all_leaves)
+        .expect("verify range proof"));
+
+    // empty range proves an empty subtree
+    let empty_range = KeyRange::new(keys[0], keys[0]);
+    let (empty_leaves, empty_proof) = tree
+        .merkle_range_proof(empty_range)
+        .expect("range proof");
+    assert!(empty_leaves.is_empty());
+    assert!(empty_proof
+        .verify::<Blake2bHasher>(tree.root(), empty_range, empty_leaves)
+        .expect("verify range proof"));
+}
+
+#[test]
+fn test_merkle_range_proof_rejects_incomplete_leaves() {
+    let mut tree = SMT::default();
+    let mut keys: Vec<H256> = Vec::new();
+    for i in 0u8..10 {
+        let mut key = H256::zero();
+        key.set_bit(i);
+        tree.update(key, [i; 32].into()).expect("update");
+        keys.push(key);
+    }
+    keys.sort_unstable();
+
+    let range = KeyRange::new(keys[2], keys[7]);
+    let (leaves, proof) = tree.merkle_range_proof(range).expect("range proof");
+
+    // hiding an in-range leaf must not still verify as "the complete set"
+    let mut incomplete_leaves = leaves.clone();
+    incomplete_leaves.remove(0);
+    assert!(!proof
+        .verify::<Blake2bHasher>(tree.root(), range, incomplete_leaves)
+        .expect("verify range proof"));
+
+    // a leaf outside the claimed range is rejected outright
+    let mut out_of_range_leaves = leaves;
+    out_of_range_leaves.push((keys[8], tree.get(&keys[8]).expect("get")));
+    assert!(proof
+        .verify::<Blake2bHasher>(tree.root(), range, out_of_range_leaves)
+        .is_err());
+}
+
+#[test]
+fn test_merkle_range_proof_rejects_minimal_proof_omitting_interior_leaf() {
+    let mut tree = SMT::default();
+    let mut keys: Vec<H256> = Vec::new();
+    for i in 0u8..10 {
+        let mut key = H256::zero();
+        key.set_bit(i);
+        tree.update(key, [i; 32].into()).expect("update");
+        keys.push(key);
+    }
+    keys.sort_unstable();
+
+    let range = KeyRange::new(keys[2], keys[7]);
+
+    // a malicious prover builds the proof itself over a reduced key set, omitting
+    // keys[4] entirely, instead of just hiding it from the verifier's leaf list
+    let included_keys: Vec<H256> = keys[2..7]
+        .iter()
+        .copied()
+        .filter(|k| *k != keys[4])
+        .collect();
+    let (minimal_leaves, minimal_proof) = tree
+        .merkle_range_proof_over(range, included_keys)
+        .expect("range proof over reduced key set");
+
+    assert!(!minimal_proof
+        .verify::<Blake2bHasher>(tree.root(), range, minimal_leaves)
+        .expect("verify range proof"));
+}
+
+#[test]
+fn test_merkle_range_proof_rejects_minimal_proof_omitting_outer_edge_leaf() {
+    let mut tree = SMT::default();
+
+    // x hangs off y's path above fork_height(y, next): y and next fork at height 2,
+    // but x and y fork at height 3, so x sits in the subtree between the range's
+    // (unbounded) left edge and y without ever being compared against y or next
+    // directly.
+    let mut x = H256::zero();
+    x.set_bit(0);
+    let mut y = H256::zero();
+    y.set_bit(3);
+    let mut next = H256::zero();
+    next.set_bit(3);
+    next.set_bit(2);
+
+    tree.update(x, [1u8; 32].into()).expect("update");
+    tree.update(y, [2u8; 32].into()).expect("update");
+    tree.update(next, [3u8; 32].into()).expect("update");
+
+    // an unbounded-start range, so there is no natural predecessor leaf below `y` to
+    // pair it against other than the synthetic left edge anchor
+    let mut end = H256::zero();
+    end.set_bit(4);
+    let range = KeyRange::to(end);
+
+    // a malicious prover builds the proof over just {y, next}, never mentioning x
+    let (minimal_leaves, minimal_proof) = tree
+        .merkle_range_proof_over(range, vec![y, next])
+        .expect("range proof over reduced key set");
+
+    assert!(!minimal_proof
+        .verify::<Blake2bHasher>(tree.root(), range, minimal_leaves)
+        .expect("verify range proof"));
+}
+
+#[cfg(feature = "trie")]
+#[test]
+fn test_compressed_store_matches_dense_root() {
+    use crate::compressed_store::CompressedSparseMerkleTree;
+
+    let mut dense = SMT::default();
+    let mut compressed = CompressedSparseMerkleTree::<Blake2bHasher, H256>::default();
+    let mut keys = Vec::new();
+    for i in 0u8..30 {
+        let mut key = H256::zero();
+        key.set_bit(i);
+        key.set_bit(i.wrapping_add(7));
+        let value: H256 = [i; 32].into();
+        dense.update(key, value).expect("update");
+        compressed.update(key, value).expect("update");
+        keys.push(key);
+    }
+
+    assert_eq!(dense.root(), compressed.root());
+    // a Patricia-compressed trie over `n` leaves stores O(n) nodes (at most ~2n forks
+    // plus n leaves), not the dense store's O(256n) branch-per-height chain
+    assert!(compressed.node_count() <= keys.len() * 8);
+    for key in &keys {
+        assert_eq!(dense.get(key).expect("get"), compressed.get(key).expect("get"));
+    }
+
+    // deleting every other key must keep the roots in sync too
+    for key in keys.iter().step_by(2) {
+        dense.update(*key, H256::zero()).expect("update");
+        compressed.update(*key, H256::zero()).expect("update");
+    }
+    assert_eq!(dense.root(), compressed.root());
+
+    let proof_keys: Vec<H256> = keys.iter().skip(1).step_by(2).cloned().collect();
+    let data: Vec<(H256, H256)> = proof_keys
+        .iter()
+        .map(|k| (*k, dense.get(k).expect("get")))
+        .collect();
+    let proof = compressed.merkle_proof(proof_keys).expect("merkle proof");
+    assert!(proof
+        .verify::<Blake2bHasher>(compressed.root(), data)
+        .expect("verify proof"));
+}
+
+#[test]
+fn test_seal() {
+    let mut tree = SMT::default();
+    let key: H256 = [1u8; 32].into();
+    let value: H256 = [42u8; 32].into();
+    tree.update(key, value).expect("update");
+    let root_before_seal = *tree.root();
+
+    tree.seal(key).expect("seal");
+    assert_eq!(tree.root(), &root_before_seal);
+    assert_eq!(tree.get(&key).unwrap_err(), Error::Sealed);
+    assert_eq!(tree.update(key, value).unwrap_err(), Error::Sealed);
+
+    // sealing again is a no-op
+    tree.seal(key).expect("seal again");
+    assert_eq!(tree.root(), &root_before_seal);
+
+    // sealing a missing key errors
+    let missing_key: H256 = [2u8; 32].into();
+    assert_eq!(tree.seal(missing_key).unwrap_err(), Error::MissingLeaf(missing_key));
+
+    // a sealed leaf can still be proven
+    let proof = tree.merkle_proof(vec![key]).expect("merkle proof");
+    assert!(proof
+        .verify::<Blake2bHasher>(tree.root(), vec![(key, value)])
+        .expect("verify proof"));
+}
+
+#[test]
+fn test_merkle_proof_byte_round_trip() {
+    use crate::merkle_proof::CompiledMerkleProof;
+
+    let mut tree = SMT::default();
+    let mut keys: Vec<H256> = Vec::new();
+    for i in 0u8..10 {
+        let mut key = H256::zero();
+        key.set_bit(i);
+        tree.update(key, [i; 32].into()).expect("update");
+        keys.push(key);
+    }
+    keys.sort_unstable();
+    let data: Vec<(H256, H256)> = keys.iter().map(|k| (*k, tree.get(k).unwrap())).collect();
+
+    let proof = tree.merkle_proof(keys.clone()).expect("merkle proof");
+    let bytes = proof.clone().to_bytes();
+    let decoded = MerkleProof::from_bytes(&bytes).expect("from_bytes");
+    assert_eq!(proof, decoded);
+    assert!(decoded
+        .verify::<Blake2bHasher>(tree.root(), data.clone())
+        .expect("verify"));
+
+    let compiled: CompiledMerkleProof =
+        CompiledMerkleProof::from_bytes(&bytes).expect("from_bytes");
+    assert!(compiled
+        .verify::<Blake2bHasher>(tree.root(), data)
+        .expect("verify"));
+
+    // a bogus version byte is rejected
+    let mut bad_version = bytes.clone();
+    bad_version[0] = 0xff;
+    assert_eq!(
+        MerkleProof::<{ core::u8::MAX as usize }>::from_bytes(&bad_version).unwrap_err(),
+        Error::InvalidCode(0xff)
+    );
+
+    // truncating the body breaks the 32-byte alignment
+    let truncated = &bytes[..bytes.len() - 1];
+    assert_eq!(
+        MerkleProof::<{ core::u8::MAX as usize }>::from_bytes(truncated).unwrap_err(),
+        Error::CorruptedProof
+    );
+}
+
+#[test]
+fn test_verify_compiled_transcript() {
+    use crate::{merkle_proof::CompiledMerkleProof, verify_compiled};
+
+    let mut tree = SMT::default();
+    let mut keys: Vec<H256> = Vec::new();
+    for i in 0u8..5 {
+        let mut key = H256::zero();
+        key.set_bit(i);
+        tree.update(key, [i; 32].into()).expect("update");
+        keys.push(key);
+    }
+    keys.sort_unstable();
+    let data: Vec<(H256, H256)> = keys.iter().map(|k| (*k, tree.get(k).unwrap())).collect();
+
+    let proof = tree.merkle_proof(keys.clone()).expect("merkle proof");
+    let format_bytes = proof.compile().to_bytes(keys.len());
+
+    // a verifier that never builds a tree can check the transcript directly
+    assert!(
+        verify_compiled::<Blake2bHasher>(tree.root(), &format_bytes, data.clone())
+            .expect("verify_compiled")
+    );
+
+    // it round-trips through CompiledMerkleProof::from_bytes too
+    let compiled: CompiledMerkleProof =
+        CompiledMerkleProof::from_bytes(&format_bytes).expect("from_bytes");
+    assert!(compiled
+        .verify::<Blake2bHasher>(tree.root(), data)
+        .expect("verify"));
+
+    // malformed transcripts are rejected rather than fed into compute_root
+    let mut bad_version = format_bytes.clone();
+    bad_version[0] = 0xff;
+    assert_eq!(
+        CompiledMerkleProof::<{ core::u8::MAX as usize }>::from_bytes(&bad_version).unwrap_err(),
+        Error::InvalidCode(0xff)
+    );
+}
+
+#[cfg(feature = "blake3")]
+#[test]
+fn test_blake3_hasher() {
+    use crate::blake3::{Blake3Hasher, Blake3Smt};
+
+    let mut tree = Blake3Smt::default();
+    let key: H256 = [1u8; 32].into();
+    let value: H256 = [42u8; 32].into();
+    tree.update(key, value).expect("update");
+    assert_ne!(tree.root(), &H256::zero());
+
+    let proof = tree.merkle_proof(vec![key]).expect("merkle proof");
+    assert!(proof
+        .verify::<Blake3Hasher>(tree.root(), vec![(key, value)])
+        .expect("verify proof"));
+}
+
+#[cfg(feature = "poseidon")]
+#[test]
+fn test_poseidon_hasher() {
+    use crate::poseidon::PoseidonHasher;
+
+    type PoseidonSMT = SparseMerkleTree<PoseidonHasher, H256, DefaultStore<H256>>;
+
+    let mut tree = PoseidonSMT::default();
+    let key: H256 = [1u8; 32].into();
+    let value: H256 = [42u8; 32].into();
+    tree.update(key, value).expect("update");
+    assert_ne!(tree.root(), &H256::zero());
+
+    let proof = tree.merkle_proof(vec![key]).expect("merkle proof");
+    assert!(proof
+        .verify::<PoseidonHasher>(tree.root(), vec![(key, value)])
+        .expect("verify proof"));
+}
+
+#[cfg(feature = "digest")]
+#[test]
+fn test_digest_hasher() {
+    use crate::digest_hasher::DigestHasher;
+    use sha2::Sha256;
+
+    type Sha256SMT = SparseMerkleTree<DigestHasher<Sha256>, H256, DefaultStore<H256>>;
+
+    let mut tree = Sha256SMT::default();
+    let key: H256 = [1u8; 32].into();
+    let value: H256 = [42u8; 32].into();
+    tree.update(key, value).expect("update");
+    assert_ne!(tree.root(), &H256::zero());
+
+    let proof = tree.merkle_proof(vec![key]).expect("merkle proof");
+    assert!(proof
+        .verify::<DigestHasher<Sha256>>(tree.root(), vec![(key, value)])
+        .expect("verify proof"));
+}
+
+#[test]
+fn test_namespaced_smt() {
+    use crate::namespaced_smt::NamespacedSmt;
+
+    type NSMT = NamespacedSmt<[u8; 16], Blake2bHasher, H256, DefaultStore<H256>>;
+
+    let mut nsmt = NSMT::default();
+    let alice = [1u8; 16];
+    let bob = [2u8; 16];
+
+    // an unused namespace reports a zero root without allocating anything
+    assert!(nsmt.is_empty(&alice));
+
+    let key: H256 = [7u8; 32].into();
+    let alice_value: H256 = [1u8; 32].into();
+    let bob_value: H256 = [2u8; 32].into();
+    nsmt.update(&alice, key, alice_value).expect("update");
+    nsmt.update(&bob, key, bob_value).expect("update");
+
+    // same key, different namespaces: isolated values and roots
+    assert_eq!(nsmt.get(&alice, &key).expect("get"), alice_value);
+    assert_eq!(nsmt.get(&bob, &key).expect("get"), bob_value);
+    assert_ne!(nsmt.root(&alice), nsmt.root(&bob));
+
+    // each namespace's tree is independently provable against its own root
+    let proof = nsmt.merkle_proof(&alice, vec![key]).expect("merkle proof");
+    assert!(proof
+        .verify::<Blake2bHasher>(&nsmt.root(&alice), vec![(key, alice_value)])
+        .expect("verify proof"));
+
+    // an unrelated namespace remains untouched
+    assert!(nsmt.is_empty(&[3u8; 16]));
+}
+
+#[test]
+fn test_namespaced_smt_variable_length_id() {
+    use crate::namespaced_smt::NamespacedSmt;
+
+    // namespace ids only need `AsRef<[u8]>`, not a fixed-size array
+    type NSMT = NamespacedSmt<Vec<u8>, Blake2bHasher, H256, DefaultStore<H256>>;
+
+    let mut nsmt = NSMT::default();
+    let short = b"a".to_vec();
+    let long = b"a much longer namespace id".to_vec();
+    let key: H256 = [7u8; 32].into();
+    let short_value: H256 = [1u8; 32].into();
+    let long_value: H256 = [2u8; 32].into();
+
+    nsmt.update(&short, key, short_value).expect("update");
+    nsmt.update(&long, key, long_value).expect("update");
+
+    assert_eq!(nsmt.get(&short, &key).expect("get"), short_value);
+    assert_eq!(nsmt.get(&long, &key).expect("get"), long_value);
+    assert_ne!(nsmt.root(&short), nsmt.root(&long));
+}
+
+#[test]
+fn test_namespaced_smt_identical_leaves_same_subroot() {
+    use crate::namespaced_smt::NamespacedSmt;
+
+    type NSMT = NamespacedSmt<[u8; 16], Blake2bHasher, H256, DefaultStore<H256>>;
+
+    let mut nsmt = NSMT::default();
+    let alice = [1u8; 16];
+    let bob = [2u8; 16];
+    let key: H256 = [7u8; 32].into();
+    let value: H256 = [9u8; 32].into();
+
+    // two namespaces given the exact same leaves compute the exact same root: the
+    // namespace only scopes where nodes land in the shared store, it never enters the
+    // hash computation itself
+    nsmt.update(&alice, key, value).expect("update");
+    nsmt.update(&bob, key, value).expect("update");
+    assert_eq!(nsmt.root(&alice), nsmt.root(&bob));
+
+    // but they stay isolated: updating one leaves the other's root untouched
+    let bob_root_before = nsmt.root(&bob);
+    nsmt.update(&alice, key, H256::zero()).expect("update");
+    assert_ne!(nsmt.root(&alice), nsmt.root(&bob));
+    assert_eq!(nsmt.root(&bob), bob_root_before);
+}
+
+#[test]
+fn test_namespaced_smt_history() {
+    use crate::namespaced_smt::NamespacedSmt;
+
+    type NSMT = NamespacedSmt<[u8; 16], Blake2bHasher, H256, DefaultStore<H256>>;
+
+    let mut nsmt = NSMT::default();
+    let alice = [1u8; 16];
+    let bob = [2u8; 16];
+    let key: H256 = [7u8; 32].into();
+
+    // a namespace that has never been updated has no history
+    assert!(nsmt.history(&alice).is_empty());
+
+    let first = nsmt.update(&alice, key, [1u8; 32].into()).expect("update");
+    let second = nsmt.update(&alice, key, [2u8; 32].into()).expect("update");
+    assert_eq!(nsmt.history(&alice), &[first, second]);
+    assert_eq!(nsmt.root(&alice), second);
+
+    // an unrelated namespace keeps its own separate history
+    assert!(nsmt.history(&bob).is_empty());
+    let bob_root = nsmt.update(&bob, key, [3u8; 32].into()).expect("update");
+    assert_eq!(nsmt.history(&bob), &[bob_root]);
+    assert_eq!(nsmt.history(&alice), &[first, second]);
+}
+
+#[cfg(feature = "rocksdb")]
+#[test]
+fn test_rocksdb_store() {
+    use crate::rocksdb_store::{self, RocksDbStore};
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    let mut tree = rocksdb_store::open_tree::<Blake2bHasher, H256, _>(dir.path()).expect("open");
+    assert_eq!(tree.root(), &H256::zero());
+
+    let key: H256 = [7u8; 32].into();
+    let value: H256 = [42u8; 32].into();
+    let root = rocksdb_store::update_all(&mut tree, vec![(key, value)]).expect("update_all");
+    assert_eq!(tree.get(&key).expect("get"), value);
+
+    // a multi-key batch must land every key, not just the last one written
+    let other_key: H256 = [9u8; 32].into();
+    let other_value: H256 = [43u8; 32].into();
+    let batch_root =
+        rocksdb_store::update_all(&mut tree, vec![(key, value), (other_key, other_value)])
+            .expect("update_all");
+    assert_eq!(tree.get(&key).expect("get"), value);
+    assert_eq!(tree.get(&other_key).expect("get"), other_value);
+    assert_eq!(tree.root(), &batch_root);
+
+    // reopening the database recovers the root without replaying any updates
+    drop(tree);
+    let reopened =
+        rocksdb_store::open_tree::<Blake2bHasher, H256, _>(dir.path()).expect("reopen");
+    assert_eq!(reopened.root(), &batch_root);
+    assert_eq!(reopened.get(&key).expect("get"), value);
+    assert_eq!(reopened.get(&other_key).expect("get"), other_value);
+    let mut store: RocksDbStore<H256> = reopened.take_store();
+
+    // clear() resets the database to the same empty state `open` finds on a fresh path
+    store.clear().expect("clear");
+    assert_eq!(store.root().expect("root"), H256::zero());
+    let cleared = SparseMerkleTree::<Blake2bHasher, H256, _>::new(H256::zero(), store);
+    assert_eq!(cleared.get(&key).expect("get"), H256::zero());
+}
+
+/// a minimal in-memory `KvBackend`, so `KvStore` can be exercised without the
+/// `rocksdb` feature; two handles share the same underlying map, standing in for
+/// "close and reopen" the way a real embedded database would persist across opens.
+#[derive(Clone, Default)]
+struct MemoryKvBackend(std::rc::Rc<core::cell::RefCell<std::collections::HashMap<Vec<u8>, Vec<u8>>>>);
+
+#[derive(Default)]
+struct MemoryKvBatch(Vec<(Vec<u8>, Option<Vec<u8>>)>);
+
+impl crate::kv_store::KvBatch for MemoryKvBatch {
+    fn put(&mut self, key: &[u8], value: &[u8]) {
+        self.0.push((key.to_vec(), Some(value.to_vec())));
+    }
+    fn delete(&mut self, key: &[u8]) {
+        self.0.push((key.to_vec(), None));
+    }
+}
+
+impl crate::kv_store::KvBackend for MemoryKvBackend {
+    type Batch = MemoryKvBatch;
+
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        Ok(self.0.borrow().get(key).cloned())
+    }
+    fn write_batch(&self, batch: MemoryKvBatch) -> Result<(), Error> {
+        let mut map = self.0.borrow_mut();
+        for (key, value) in batch.0 {
+            match value {
+                Some(value) => {
+                    map.insert(key, value);
+                }
+                None => {
+                    map.remove(&key);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_kv_store() {
+    use crate::kv_store::{self, KvStore};
+
+    let backend = MemoryKvBackend::default();
+    let mut tree = kv_store::open_tree::<Blake2bHasher, H256, _>(backend.clone()).expect("open");
+    assert_eq!(tree.root(), &H256::zero());
+
+    let key: H256 = [7u8; 32].into();
+    let value: H256 = [42u8; 32].into();
+    let root = kv_store::update_all(&mut tree, vec![(key, value)]).expect("update_all");
+    assert_eq!(tree.get(&key).expect("get"), value);
+
+    // a multi-key batch must land every key, not just the last one written
+    let other_key: H256 = [9u8; 32].into();
+    let other_value: H256 = [43u8; 32].into();
+    let batch_root = kv_store::update_all(&mut tree, vec![(key, value), (other_key, other_value)])
+        .expect("update_all");
+    assert_eq!(tree.get(&key).expect("get"), value);
+    assert_eq!(tree.get(&other_key).expect("get"), other_value);
+    assert_eq!(tree.root(), &batch_root);
+
+    // a fresh `KvStore` over the same backend recovers the root from the dedicated
+    // root key without replaying any updates, just like `RocksDbStore`
+    drop(tree);
+    let reopened = kv_store::open_tree::<Blake2bHasher, H256, _>(backend).expect("reopen");
+    assert_eq!(reopened.root(), &batch_root);
+    assert_eq!(reopened.get(&key).expect("get"), value);
+    assert_eq!(reopened.get(&other_key).expect("get"), other_value);
+
+    // and the store taken back out of the reopened tree can back a new tree pinned
+    // to that same root
+    let store: KvStore<MemoryKvBackend, H256> = reopened.take_store();
+    let pinned = SparseMerkleTree::<Blake2bHasher, H256, _>::new(batch_root, store);
+    assert_eq!(pinned.get(&key).expect("get"), value);
+    assert_eq!(pinned.get(&other_key).expect("get"), other_value);
+}
+
+#[test]
+fn test_update_opt_get_opt() {
+    let mut tree = SMT::default();
+    let key: H256 = [1u8; 32].into();
+
+    // never set
+    assert_eq!(tree.get_opt(&key).expect("get_opt"), None);
+    assert_eq!(tree.get(&key).expect("get"), H256::zero());
+
+    // explicitly stored as zero: get_opt sees it, get still reports zero, and the
+    // root is unaffected since it keeps the same hash contribution as no leaf at all
+    let root_before = *tree.root();
+    tree.update_opt(key, Some(H256::zero())).expect("update_opt");
+    assert_eq!(tree.get_opt(&key).expect("get_opt"), Some(H256::zero()));
+    assert_eq!(tree.get(&key).expect("get"), H256::zero());
+    assert_eq!(tree.root(), &root_before);
+
+    // update_opt(None) deletes, same as update(key, zero)
+    tree.update_opt(key, None).expect("update_opt");
+    assert_eq!(tree.get_opt(&key).expect("get_opt"), None);
+
+    // a real value round-trips through both APIs
+    let value: H256 = [9u8; 32].into();
+    tree.update_opt(key, Some(value)).expect("update_opt");
+    assert_eq!(tree.get_opt(&key).expect("get_opt"), Some(value));
+    assert_eq!(tree.get(&key).expect("get"), value);
+
+    // compute_root_opt / verify_opt can prove non-membership with None
+    let other_key: H256 = [2u8; 32].into();
+    let proof = tree
+        .merkle_proof(vec![key, other_key])
+        .expect("merkle proof");
+    assert!(proof
+        .verify_opt::<Blake2bHasher>(tree.root(), vec![(key, Some(value)), (other_key, None)])
+        .expect("verify_opt"));
+}
+
+#[test]
+fn test_verify_opt_rejects_wrong_membership_claim() {
+    let mut tree = SMT::default();
+    let present_key: H256 = [3u8; 32].into();
+    let present_value: H256 = [4u8; 32].into();
+    let absent_key: H256 = [5u8; 32].into();
+    tree.update(present_key, present_value).expect("update");
+
+    let proof = tree
+        .merkle_proof(vec![present_key, absent_key])
+        .expect("merkle proof");
+
+    // claiming the present key is absent, or the absent key holds some value,
+    // recomputes a different root than the one actually on the tree, so `verify_opt`
+    // rejects a proof replayed with the wrong membership assumption
+    assert!(!proof
+        .clone()
+        .verify_opt::<Blake2bHasher>(
+            tree.root(),
+            vec![(present_key, None), (absent_key, None)],
+        )
+        .expect("verify_opt"));
+    assert!(!proof
+        .verify_opt::<Blake2bHasher>(
+            tree.root(),
+            vec![(present_key, Some(present_value)), (absent_key, Some(present_value))],
+        )
+        .expect("verify_opt"));
+}
+
+#[test]
+fn test_get_with_proof() {
+    let mut tree = SMT::default();
+    let key: H256 = [5u8; 32].into();
+    let value: H256 = [9u8; 32].into();
+    tree.update(key, value).expect("update");
+
+    let (got, compiled) = tree.get_with_proof(key).expect("get_with_proof");
+    assert_eq!(got, Some(value));
+    assert!(compiled
+        .verify::<Blake2bHasher>(tree.root(), vec![(key, value)])
+        .expect("verify"));
+
+    // a never-set key reports `None` but still proves its own non-inclusion
+    let other_key: H256 = [6u8; 32].into();
+    let (missing, compiled) = tree.get_with_proof(other_key).expect("get_with_proof");
+    assert_eq!(missing, None);
+    assert!(compiled
+        .verify::<Blake2bHasher>(tree.root(), vec![(other_key, H256::zero())])
+        .expect("verify"));
+
+    // batch variant covers every key with a single shared proof
+    let (values, compiled) = tree
+        .get_with_proof_all(vec![key, other_key])
+        .expect("get_with_proof_all");
+    assert_eq!(values, vec![Some(value), None]);
+    assert!(compiled
+        .verify::<Blake2bHasher>(
+            tree.root(),
+            vec![(key, value), (other_key, H256::zero())]
+        )
+        .expect("verify"));
+}
+
+#[test]
+fn test_small_depth_tree_round_trip() {
+    // confine keys to the low 32 bits (heights 0..=31); every other bit stays zero,
+    // so a DEPTH=31 tree sees each of these keys as distinct.
+    type SmallDepthSmt = SparseMerkleTree<Blake2bHasher, H256, DefaultStore<H256>, 31>;
+
+    let mut tree = SmallDepthSmt::default();
+    let keys: Vec<H256> = (0u32..8)
+        .map(|i| {
+            let mut key = H256::zero();
+            for bit in 0..32u8 {
+                if (i >> bit) & 1 == 1 {
+                    key.set_bit(bit);
+                }
+            }
+            key
+        })
+        .collect();
+
+    for (i, key) in keys.iter().enumerate() {
+        tree.update(*key, [i as u8; 32].into()).expect("update");
+    }
+    for (i, key) in keys.iter().enumerate() {
+        assert_eq!(tree.get(key).expect("get"), [i as u8; 32].into());
+    }
+
+    let proof = tree.merkle_proof(keys.clone()).expect("merkle_proof");
+    let leaves: Vec<(H256, H256)> = keys
+        .iter()
+        .enumerate()
+        .map(|(i, key)| (*key, H256::from([i as u8; 32])))
+        .collect();
+    assert!(proof
+        .clone()
+        .verify::<Blake2bHasher>(tree.root(), leaves.clone())
+        .expect("verify"));
+
+    // the compiled form round-trips through bytes the same way
+    let compiled_bytes: Vec<u8> = proof.compile().to_bytes(keys.len());
+    let compiled: CompiledMerkleProof<31> =
+        CompiledMerkleProof::from_bytes(&compiled_bytes).expect("from_bytes");
+    assert!(compiled
+        .verify::<Blake2bHasher>(tree.root(), leaves)
+        .expect("verify compiled"));
+}
+
+#[test]
+fn test_update_all_matches_sequential_updates() {
+    let mut batched = SMT::default();
+    let mut sequential = SMT::default();
+
+    let mut pairs = Vec::new();
+    for i in 0u8..20 {
+        let mut key = H256::zero();
+        key.set_bit(i);
+        key.set_bit(i.wrapping_add(3));
+        let value: H256 = [i; 32].into();
+        pairs.push((key, value));
+        sequential.update(key, value).expect("update");
+    }
+    batched.update_all(pairs.clone()).expect("update_all");
+    assert_eq!(batched.root(), sequential.root());
+    for (key, value) in &pairs {
+        assert_eq!(batched.get(key).expect("get"), *value);
+    }
+
+    // a later pair for a repeated key overrides the earlier one, like calling
+    // `update` twice would
+    let (first_key, _) = pairs[0];
+    let overridden_value: H256 = [99u8; 32].into();
+    let mut with_override = pairs.clone();
+    with_override.push((first_key, overridden_value));
+    let mut batched_override = SMT::default();
+    batched_override
+        .update_all(with_override)
+        .expect("update_all");
+    assert_eq!(
+        batched_override.get(&first_key).expect("get"),
+        overridden_value
+    );
+
+    // deletions (zero value) behave the same batched as sequential
+    let deletes: Vec<(H256, H256)> = pairs
+        .iter()
+        .step_by(2)
+        .map(|(key, _)| (*key, H256::zero()))
+        .collect();
+    for (key, value) in &deletes {
+        sequential.update(*key, *value).expect("update");
+    }
+    batched.update_all(deletes).expect("update_all");
+    assert_eq!(batched.root(), sequential.root());
+}
+
+#[test]
+fn test_vec_u8_leaf_value() {
+    let mut tree: SparseMerkleTree<Blake2bHasher, Vec<u8>, DefaultStore<Vec<u8>>> =
+        Default::default();
+    let key: H256 = [3u8; 32].into();
+    let value = b"arbitrary application record".to_vec();
+
+    tree.update(key, value.clone()).expect("update");
+    assert_eq!(tree.get(&key).expect("get"), value);
+
+    // the root only ever commits the 32-byte `leaf_hash`, not the raw bytes
+    assert_ne!(value.leaf_hash::<Blake2bHasher>(), H256::zero());
+    assert_ne!(tree.root(), &H256::zero());
+
+    // deleting is still "update to the empty value"
+    tree.update(key, Vec::new()).expect("update");
+    assert_eq!(tree.get(&key).expect("get"), Vec::new());
+    assert!(tree.is_empty());
+}
+
+#[test]
+fn test_merkle_proof_cost() {
+    let mut tree = SMT::default();
+    let keys: Vec<H256> = (1u8..=4).map(|i| [i; 32].into()).collect();
+    for key in &keys {
+        tree.update(*key, [9u8; 32].into()).expect("update");
+    }
+
+    let proof = tree.merkle_proof(keys.clone()).expect("merkle proof");
+    let cost = proof.cost(keys.clone()).expect("cost");
+    assert_eq!(cost.sibling_hashes, proof.proof().len());
+    assert_eq!(cost.leaf_hashes, keys.len());
+    assert_eq!(cost.hash_invocations(), cost.leaf_hashes + cost.merge_hashes);
+
+    // the compiled proof reports the same cost as the uncompiled one
+    let compiled_cost = proof.clone().compile().cost(keys.clone()).expect("cost");
+    assert_eq!(compiled_cost, cost);
+
+    // wrong key count is rejected the same way compute_root rejects it
+    assert_eq!(
+        proof.cost(vec![keys[0]]).unwrap_err(),
+        Error::IncorrectNumberOfLeaves {
+            expected: keys.len(),
+            actual: 1,
+        }
+    );
+}
+
+#[test]
+fn test_counting_store() {
+    use crate::counting_store::CountingStore;
+    use core::sync::atomic::Ordering;
+
+    let mut tree: SparseMerkleTree<Blake2bHasher, H256, CountingStore<DefaultStore<H256>>> =
+        Default::default();
+    let key: H256 = [7u8; 32].into();
+
+    tree.update(key, [8u8; 32].into()).expect("update");
+    assert!(tree.store().counters().insert_branch.load(Ordering::Relaxed) > 0);
+    assert_eq!(tree.store().counters().insert_leaf.load(Ordering::Relaxed), 1);
+
+    tree.get(&key).expect("get");
+    assert!(tree.store().counters().get_leaf.load(Ordering::Relaxed) > 0);
+}
+
 fn test_construct(key: H256, value: H256) {
     // insert same value to sibling key will construct a different root
 
@@ -1,6 +1,7 @@
 use crate::{
     error::Error,
     tree::{BranchKey, BranchNode, LeafNode},
+    vec::Vec,
     H256,
 };
 
@@ -26,6 +27,64 @@ impl Value for H256 {
     }
 }
 
+/// Trait for leaf values whose byte representation is folded into the 32-byte
+/// digest the tree commits to, via the tree's own `Hasher`, rather than requiring
+/// every `V` to hand-roll its own hash the way `Value::to_h256` does. This lets the
+/// tree hold arbitrary payloads (`Vec<u8>`, structured records, ...) instead of only
+/// pre-hashed 32-byte values.
+pub trait Leaf {
+    /// the leaf's full byte representation
+    fn bytes(&self) -> &[u8];
+
+    /// fold `bytes()` into the 32-byte digest committed in the tree; empty bytes
+    /// hash to `H256::zero()`, keeping "empty value deletes the key" consistent
+    fn leaf_hash<H: Hasher + Default>(&self) -> H256 {
+        let bytes = self.bytes();
+        if bytes.is_empty() {
+            return H256::zero();
+        }
+        let mut hasher = H::default();
+        for b in bytes {
+            hasher.write_byte(*b);
+        }
+        hasher.finish()
+    }
+}
+
+impl Leaf for H256 {
+    fn bytes(&self) -> &[u8] {
+        self.as_slice()
+    }
+    /// `H256` values already *are* the 32-byte digest, so existing behavior is
+    /// unchanged: no re-hashing, just the value itself.
+    fn leaf_hash<H: Hasher + Default>(&self) -> H256 {
+        *self
+    }
+}
+
+impl Leaf for Vec<u8> {
+    fn bytes(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+/// `to_h256` has no `Hasher` to fold the bytes with, so it can't double as the
+/// value's tree commitment the way `H256::to_h256` does; the tree itself only ever
+/// calls `Leaf::leaf_hash`. This just copies (truncating or zero-padding) the raw
+/// bytes, so callers outside the tree still get a cheap `Value`; it is zero for an
+/// empty vec, and also for any vec whose first 32 bytes are all zero.
+impl Value for Vec<u8> {
+    fn to_h256(&self) -> H256 {
+        let mut buf = [0u8; 32];
+        let len = self.len().min(32);
+        buf[..len].copy_from_slice(&self[..len]);
+        buf.into()
+    }
+    fn zero() -> Self {
+        Vec::new()
+    }
+}
+
 /// Trait for customize backend storage
 pub trait Store<V> {
     fn get_branch(&self, branch_key: &BranchKey) -> Result<Option<BranchNode>, Error>;
@@ -1,15 +1,16 @@
 use crate::{
+    default_store::Map,
     error::{Error, Result},
     merge::{hash_leaf, merge},
-    merkle_proof::MerkleProof,
-    traits::{Hasher, Store, Value},
+    merkle_proof::{CompiledMerkleProof, MerkleProof},
+    traits::{Hasher, Leaf, Store, Value},
     vec::Vec,
     H256,
 };
 use core::marker::PhantomData;
 
 /// The branch key
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 pub struct BranchKey {
     height: u8,
     node_key: H256,
@@ -19,6 +20,12 @@ impl BranchKey {
     pub fn new(height: u8, node_key: H256) -> BranchKey {
         BranchKey { height, node_key }
     }
+    pub fn height(&self) -> u8 {
+        self.height
+    }
+    pub fn node_key(&self) -> H256 {
+        self.node_key
+    }
 }
 
 /// A branch in the SMT
@@ -28,24 +35,89 @@ pub struct BranchNode {
     right: H256,
 }
 
+impl BranchNode {
+    pub fn new(left: H256, right: H256) -> Self {
+        BranchNode { left, right }
+    }
+    pub fn left(&self) -> H256 {
+        self.left
+    }
+    pub fn right(&self) -> H256 {
+        self.right
+    }
+}
+
 /// A leaf in the SMT
+///
+/// A `Sealed` leaf no longer carries its value: the value is gone for good, but the
+/// leaf's hash is retained so it keeps contributing to `root()` and can still be
+/// included in a `merkle_proof`, exactly as a `Live` leaf would.
 #[derive(Debug, Eq, PartialEq, Clone)]
-pub struct LeafNode<V> {
-    pub key: H256,
-    pub value: V,
+pub enum LeafNode<V> {
+    Live { key: H256, value: V },
+    Sealed { key: H256, hash: H256 },
+}
+
+impl<V> LeafNode<V> {
+    pub fn key(&self) -> H256 {
+        match self {
+            LeafNode::Live { key, .. } | LeafNode::Sealed { key, .. } => *key,
+        }
+    }
+}
+
+impl<V: Value> LeafNode<V> {
+    /// the stored value, or `Error::Sealed` if this leaf has been sealed
+    pub fn into_value(self) -> Result<V> {
+        match self {
+            LeafNode::Live { value, .. } => Ok(value),
+            LeafNode::Sealed { .. } => Err(Error::Sealed),
+        }
+    }
+}
+
+impl<V: Leaf> LeafNode<V> {
+    /// hash this leaf contributes to the tree, whether it's live or sealed
+    pub fn hash<H: Hasher + Default>(&self) -> H256 {
+        match self {
+            LeafNode::Live { key, value } => hash_leaf::<H>(key, &value.leaf_hash::<H>()),
+            LeafNode::Sealed { hash, .. } => *hash,
+        }
+    }
 }
 
 /// Sparse merkle tree
+///
+/// `DEPTH` is the highest tree height a key's bits reach (heights `0..=DEPTH`, so
+/// `DEPTH + 1` bit-levels), defaulting to the full 256-bit `H256` key space. Height counts from
+/// the least significant bit (`H256::get_bit(0)`, the low bit of the key's first
+/// byte) up, so a `DEPTH < 255` tree only ever inspects a key's *low*-order bits and
+/// never looks at the bits above `DEPTH`: `SparseMerkleTree<H, V, S, 127>` walks and
+/// proves only the low 128 bits, so `update`/`merkle_proof` touch half as many
+/// heights and proofs carry at most half as many sibling hashes. Keys are only
+/// distinguished by this tree if they differ somewhere in the low `DEPTH + 1` bits;
+/// two keys that agree there but differ only above `DEPTH` still alias to the same
+/// leaf, so callers must keep those high bits identical (typically zero) across
+/// every key they store. Note `H256::Ord`/`fork_height` compare the *entire* 256-bit
+/// key from the high bit down, so they (and anything built on them, like
+/// `RangeProof`) stay meaningful only on a default, full-`DEPTH` tree; they are not
+/// generic over `DEPTH` and aren't available on a custom-`DEPTH` instantiation.
 #[derive(Default, Debug)]
-pub struct SparseMerkleTree<H, V, S> {
+pub struct SparseMerkleTree<H, V, S, const DEPTH: usize = { core::u8::MAX as usize }> {
     store: S,
     root: H256,
-    phantom: PhantomData<(H, V)>,
+    phantom: PhantomData<(H, V, [(); DEPTH])>,
 }
 
-impl<H: Hasher + Default, V: Value, S: Store<V>> SparseMerkleTree<H, V, S> {
+impl<H: Hasher + Default, V: Value + Leaf, S: Store<V>, const DEPTH: usize>
+    SparseMerkleTree<H, V, S, DEPTH>
+{
     /// Build a merkle tree from root and store
-    pub fn new(root: H256, store: S) -> SparseMerkleTree<H, V, S> {
+    pub fn new(root: H256, store: S) -> SparseMerkleTree<H, V, S, DEPTH> {
+        debug_assert!(
+            DEPTH <= core::u8::MAX as usize,
+            "DEPTH must fit in a u8 tree height"
+        );
         SparseMerkleTree {
             root,
             store,
@@ -81,19 +153,146 @@ impl<H: Hasher + Default, V: Value, S: Store<V>> SparseMerkleTree<H, V, S> {
     /// Update a leaf, return new merkle root
     /// set to zero value to delete a key
     pub fn update(&mut self, key: H256, value: V) -> Result<&H256> {
+        if let Some(LeafNode::Sealed { .. }) = self.store.get_leaf(&key)? {
+            return Err(Error::Sealed);
+        }
+
         // compute and store new leaf
-        let node = hash_leaf::<H>(&key, &value.to_h256());
+        let node = hash_leaf::<H>(&key, &value.leaf_hash::<H>());
         // notice when value is zero the leaf is deleted, so we do not need to store it
         if !node.is_zero() {
-            self.store.insert_leaf(key, LeafNode { key, value })?;
+            self.store.insert_leaf(key, LeafNode::Live { key, value })?;
         } else {
             self.store.remove_leaf(&key)?;
         }
 
-        // recompute the tree from bottom to top
+        self.recompute_root(key, node)
+    }
+
+    /// Update a leaf with `None`/`Some(value)`, distinguishing "delete" (`None`) from
+    /// "explicitly store the zero value" (`Some(V::zero())`). Unlike `update`, the
+    /// latter still keeps a leaf record so `get_opt` can report it, even though the
+    /// zero value's hash contribution to `root()` is the same either way.
+    pub fn update_opt(&mut self, key: H256, value: Option<V>) -> Result<&H256> {
+        if let Some(LeafNode::Sealed { .. }) = self.store.get_leaf(&key)? {
+            return Err(Error::Sealed);
+        }
+
+        match value {
+            Some(value) => {
+                let node = hash_leaf::<H>(&key, &value.leaf_hash::<H>());
+                self.store.insert_leaf(key, LeafNode::Live { key, value })?;
+                self.recompute_root(key, node)
+            }
+            None => {
+                self.store.remove_leaf(&key)?;
+                self.recompute_root(key, H256::zero())
+            }
+        }
+    }
+
+    /// Apply every `(key, value)` pair at once, sharing the recomputation between
+    /// keys that pass through the same ancestor instead of walking all 256 heights
+    /// once per key the way repeated `update` calls would. A later pair for a key
+    /// already seen in `pairs` overrides the earlier one, matching what calling
+    /// `update` for each pair in order would leave behind.
+    ///
+    /// Leaves are written immediately, same as `update`. Branch nodes are only
+    /// touched once per dirty ancestor: at each height, keys whose paths still
+    /// collide are merged in-memory and carried up together, so an ancestor shared by
+    /// many of the updated keys is hashed and written exactly once instead of once
+    /// per key — turning `pairs.len()` scattered updates from roughly
+    /// `O(pairs.len() * 256)` hashes into `O(dirty ancestors)`.
+    pub fn update_all(&mut self, pairs: Vec<(H256, V)>) -> Result<&H256> {
+        if pairs.is_empty() {
+            return Ok(&self.root);
+        }
+
+        // stage every leaf write now; a later pair for the same key wins
+        let mut node_for: Map<H256, H256> = Map::default();
+        for (key, value) in pairs {
+            if let Some(LeafNode::Sealed { .. }) = self.store.get_leaf(&key)? {
+                return Err(Error::Sealed);
+            }
+            let node = hash_leaf::<H>(&key, &value.leaf_hash::<H>());
+            if !node.is_zero() {
+                self.store.insert_leaf(key, LeafNode::Live { key, value })?;
+            } else {
+                self.store.remove_leaf(&key)?;
+            }
+            node_for.insert(key, node);
+        }
+
+        let mut current_keys: Vec<H256> = node_for.keys().copied().collect();
+        current_keys.sort_unstable();
+
+        for height in 0..=(DEPTH as u8) {
+            let mut next_keys: Vec<H256> = Vec::with_capacity(current_keys.len());
+            let mut key_idx = 0;
+            while key_idx < current_keys.len() {
+                let key_a = current_keys[key_idx];
+                let parent_key = key_a.parent_path(height);
+                let parent_branch_key = BranchKey::new(height, parent_key);
+                let node_a = *node_for.get(&key_a).expect("dirty node recorded");
+
+                // a still-dirty sibling is adjacent in `current_keys`, since the two
+                // only differ in their bit at `height`
+                let sibling = current_keys
+                    .get(key_idx + 1)
+                    .copied()
+                    .filter(|key_b| key_b.parent_path(height) == parent_key);
+
+                let (left, right) = if let Some(key_b) = sibling {
+                    let node_b = *node_for.get(&key_b).expect("dirty node recorded");
+                    if key_a.is_right(height) {
+                        (node_b, node_a)
+                    } else {
+                        (node_a, node_b)
+                    }
+                } else {
+                    let other = self
+                        .store
+                        .get_branch(&parent_branch_key)?
+                        .map_or(H256::zero(), |branch| {
+                            if key_a.is_right(height) {
+                                branch.left
+                            } else {
+                                branch.right
+                            }
+                        });
+                    if key_a.is_right(height) {
+                        (other, node_a)
+                    } else {
+                        (node_a, other)
+                    }
+                };
+
+                if !left.is_zero() || !right.is_zero() {
+                    self.store
+                        .insert_branch(parent_branch_key, BranchNode { left, right })?;
+                } else {
+                    self.store.remove_branch(&parent_branch_key)?;
+                }
+
+                node_for.insert(key_a, merge::<H>(height, &parent_key, &left, &right));
+                next_keys.push(key_a);
+                key_idx += if sibling.is_some() { 2 } else { 1 };
+            }
+            current_keys = next_keys;
+        }
+
+        self.root = current_keys
+            .first()
+            .and_then(|key| node_for.get(key).copied())
+            .unwrap_or_else(H256::zero);
+        Ok(&self.root)
+    }
+
+    /// recompute the tree from `key`'s leaf (already hashed to `node`) up to the root
+    fn recompute_root(&mut self, key: H256, node: H256) -> Result<&H256> {
         let mut current_key = key;
         let mut current_node = node;
-        for height in 0..=core::u8::MAX {
+        for height in 0..=(DEPTH as u8) {
             let parent_key = current_key.parent_path(height);
             let parent_branch_key = BranchKey::new(height, parent_key);
             let (left, right) =
@@ -128,19 +327,72 @@ impl<H: Hasher + Default, V: Value, S: Store<V>> SparseMerkleTree<H, V, S> {
 
     /// Get value of a leaf
     /// return zero value if leaf not exists
+    /// return `Error::Sealed` if the leaf has been sealed
     pub fn get(&self, key: &H256) -> Result<V> {
         if self.is_empty() {
             return Ok(V::zero());
         }
-        Ok(self
-            .store
-            .get_leaf(key)?
-            .map(|node| node.value)
-            .unwrap_or_else(V::zero))
+        match self.store.get_leaf(key)? {
+            Some(leaf) => leaf.into_value(),
+            None => Ok(V::zero()),
+        }
+    }
+
+    /// Get a leaf's value, distinguishing "never set" (`None`) from "explicitly
+    /// stored as the zero value" (`Some(V::zero())`) left behind by `update_opt`.
+    /// `get` cannot make this distinction: both collapse to `V::zero()`.
+    pub fn get_opt(&self, key: &H256) -> Result<Option<V>> {
+        match self.store.get_leaf(key)? {
+            Some(leaf) => Ok(Some(leaf.into_value()?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Seal a leaf: its value can no longer be read or updated, but its hash keeps
+    /// contributing to `root()` exactly as before, so sealing never changes the root,
+    /// and the leaf can still be included in a `merkle_proof`. Sealing an
+    /// already-sealed key is a no-op; sealing a key that isn't in the tree errors.
+    pub fn seal(&mut self, key: H256) -> Result<()> {
+        match self.store.get_leaf(&key)? {
+            Some(LeafNode::Live { value, .. }) => {
+                let hash = hash_leaf::<H>(&key, &value.leaf_hash::<H>());
+                self.store.insert_leaf(key, LeafNode::Sealed { key, hash })?;
+                Ok(())
+            }
+            Some(LeafNode::Sealed { .. }) => Ok(()),
+            None => Err(Error::MissingLeaf(key)),
+        }
+    }
+
+    /// Fetch `key`'s value together with a compiled merkle proof for it, so a
+    /// light-client server answering a read can hand back a proof bytes blob (ready
+    /// for `CompiledMerkleProof::verify`, the same one the FFI `SmtCImpl::verify` path
+    /// consumes) as one call instead of two. This is purely a convenience wrapper
+    /// around `get_opt` followed by `merkle_proof`/`compile`: it still walks the
+    /// tree (and the store) twice, once for each, rather than collecting the proof
+    /// during the value lookup.
+    pub fn get_with_proof(&self, key: H256) -> Result<(Option<V>, CompiledMerkleProof<DEPTH>)> {
+        let value = self.get_opt(&key)?;
+        let proof = self.merkle_proof(vec![key])?.compile();
+        Ok((value, proof))
+    }
+
+    /// Batch variant of `get_with_proof`: every key's value alongside one compiled
+    /// proof covering all of them.
+    pub fn get_with_proof_all(
+        &self,
+        keys: Vec<H256>,
+    ) -> Result<(Vec<Option<V>>, CompiledMerkleProof<DEPTH>)> {
+        let values = keys
+            .iter()
+            .map(|key| self.get_opt(key))
+            .collect::<Result<Vec<_>>>()?;
+        let proof = self.merkle_proof(keys)?.compile();
+        Ok((values, proof))
     }
 
     /// Generate merkle proof
-    pub fn merkle_proof(&self, mut keys: Vec<H256>) -> Result<MerkleProof> {
+    pub fn merkle_proof(&self, mut keys: Vec<H256>) -> Result<MerkleProof<DEPTH>> {
         if keys.is_empty() {
             return Err(Error::EmptyKeys);
         }
@@ -152,7 +404,7 @@ impl<H: Hasher + Default, V: Value, S: Store<V>> SparseMerkleTree<H, V, S> {
         let mut leaves_path: Vec<H256> = Default::default();
         for current_key in &keys {
             let mut path = H256::zero();
-            for height in 0..=core::u8::MAX {
+            for height in 0..=(DEPTH as u8) {
                 let parent_key = current_key.parent_path(height);
                 let parent_branch_key = BranchKey::new(height, parent_key);
                 if let Some(parent_branch) = self.store.get_branch(&parent_branch_key)? {
@@ -175,7 +427,7 @@ impl<H: Hasher + Default, V: Value, S: Store<V>> SparseMerkleTree<H, V, S> {
         let mut proof: Vec<H256> = Default::default();
         let mut current_keys: Vec<H256> = keys;
         let mut next_keys: Vec<H256> = Default::default();
-        for height in 0..=core::u8::MAX {
+        for height in 0..=(DEPTH as u8) {
             let mut key_idx = 0;
             while key_idx < current_keys.len() {
                 let key_a = current_keys[key_idx];